@@ -1,31 +1,96 @@
-use std::io::{self, Write};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Cursor, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
 
-use parser::{AddChildParams, AddParams, CreateParams, StockParams, TreeParams, WhereUsedParams};
-use pbs_core::{Result, Store};
+use output::{AttributeRecord, ItemRecord, TreeRecord, UsageRecord};
+use parser::{
+    AddChildParams, AddParams, CreateParams, DeleteParams, ExportBomParams, ExportGraphParams,
+    GetAttrParams, ImportCsvParams, ListParams, OutputFormat, PnSchemeParams, RemoveAttrParams,
+    RemoveChildParams, ReviseParams, RevisionsParams, RunParams, SearchParams, SetAttrParams,
+    SetMaturityParams, SetQuantityParams, StockParams, TreeParams, UnitParams, WhereUsedParams,
+};
+use pbs_core::{
+    AttributeValue, Attributes, Error, GraphFormat, ImportReport, Item, ItemQuantity, Quantity,
+    Result, Store,
+};
 
 use crate::parser::{get_command, Command};
 
+mod output;
 mod parser;
 
+/// The default store, in the current directory
 const STORE_URI: &str = "store.db3";
 
 const COMMANDS: &str = r#"
  - help                                           This help
  - exit                                           Exit the pbs CLI
+ - create <NAME> [--category <CATEGORY>]          Create an item with a new PN, allocated by the PN scheme of the store
+                                                  (in the given category for the `prefixed` scheme)
  - add <PART_NUMBER> <NAME>                       Add a item to the store
- - list                                           List all items in the store
+ - list [--output <FORMAT>]                       List all items in the store
+ - search <PATTERN> [--maturity <MATURITY>]       Search the items whose PN or name contains <PATTERN>, or matches it
+          [--version <N>]                         if it is a glob pattern like `305.*` (only the items with the given
+          [--attr <KEY>=<VALUE>]...               maturity with `--maturity`, the given version with `--version`,
+          [--output <FORMAT>]                     and the given attribute values with `--attr`)
+ - set-attr <PN> <KEY> <VALUE> [--type <TYPE>]    Set an attribute of an item, e.g. `set-attr PN weight 2.5 --type number`
+ - get-attr <PN> [<KEY>] [--output <FORMAT>]      Show the attributes of an item, or only the given one
+ - remove-attr <PN> <KEY>                         Remove an attribute from an item
+ - unit <PN> [<UNIT>]                             Show or set the unit of measure of an item
  - add-child <PARENT_PN> <CHILD_PN> <QUANTITY>    Add a child item to an parent item
- - tree <PN>                                      Show the children of an item
- - where-used <PN>                                Show all items where the given <PN> is used"#;
+ - remove-child <PARENT_PN> <CHILD_PN>            Remove a child item from a parent item
+ - set-qty <PARENT_PN> <CHILD_PN> <QUANTITY>      Change the quantity of a child item in a parent item
+ - delete <PN> [--force]                          Delete the last revision of an item
+                                                  (even if it is used, with `--force`)
+ - tree <PN> [--depth <N>] [--output <FORMAT>]    Show the multi-level structure of an item
+                                                  (limited to <N> levels with `--depth`)
+ - where-used <PN> [--all] [--output <FORMAT>]    Show all items where the given <PN> is used
+                                                  (up to the top level products with `--all`)
+ - stock <PN> [--leaves] [--output <FORMAT>]      Show the total quantity of all items composing <PN>
+                                                  (only the leaf items with `--leaves`)
+ - review <PN>                                    Submit an item for review
+ - release <PN>                                   Release an item (all its children must be released)
+ - obsolete <PN>                                  Make a released item obsolete
+ - revise <PN>                                    Create a new revision of a released item
+ - revisions <PN>                                 Show all the revisions of an item
+ - check                                          Check that the product structure contains no cycle
+ - run <FILE> [--stop-on-error] [--transaction]   Run all the commands of a script file
+                                                  (until the first failing line with `--stop-on-error`,
+                                                  cancelling all its changes on failure with `--transaction`)
+ - import-csv [--items <FILE>] [--links <FILE>]   Import items (pn, name, other columns as attributes) and links
+              [--columns <MAPPING>] [--dry-run]   (parent_pn, child_pn, quantity) from CSV files, nothing is imported
+                                                  if a row fails
+                                                  (columns renamed with e.g. `--columns "pn=Part Number,quantity=Qty"`,
+                                                  only showing the changes with `--dry-run`)
+ - export-bom <PN> <FILE>                         Export the multi-level BOM of <PN> as an indented CSV file,
+                                                  or as an .xlsx workbook if <FILE> ends with `.xlsx`
+ - export-graph <PN> <FILE>                       Export the structure of <PN> as a Graphviz DOT graph,
+                                                  or as a Mermaid flowchart if <FILE> ends with `.mmd`
+ - pn-scheme [<SCHEME>]                           Show or set the PN scheme of the store : `simple` (00000123),
+                                                  `prefixed` (ASM-000123), `date` (20240131-0001) or `luhn` (00001230,
+                                                  with a check digit), the PNs added manually must then follow it
+
+<FORMAT> is `text` (the default), `json` or `csv`
+<MATURITY> is `in-progress`, `in-review`, `released` or `obsolete`
+<TYPE> is `text` (the default), `number`, `bool` (`true` or `false`) or `date` (`YYYY-MM-DD`)
+<UNIT> is `each` (the default), `mm`, `cm`, `m`, `m2`, `mL`, `L`, `m3`, `g`, `kg` or `t`
+<QUANTITY> is a positive decimal number in the unit of the child item, or followed by a compatible unit,
+e.g. `2.5` or `"500 mm"`"#;
 
 struct PbsCli {
     store: Store,
+    /// Canonical paths of the scripts being run, the innermost last
+    running: Vec<PathBuf>,
 }
 
 impl PbsCli {
-    fn new() -> Result<Self> {
+    fn new(store_uri: &str) -> Result<Self> {
         Ok(PbsCli {
-            store: Store::open(STORE_URI)?,
+            store: Store::open(store_uri)?,
+            running: Vec::new(),
         })
     }
 
@@ -37,100 +102,679 @@ impl PbsCli {
         Ok(input)
     }
 
-    fn handle_cmd(&mut self, cmd: Command) {
-        match cmd {
+    /// Run a command, the errors being given as their message
+    fn handle_cmd(&mut self, cmd: Command) -> std::result::Result<(), String> {
+        let result = match cmd {
             Command::Create(params) => self.handle_create(params),
             Command::Add(params) => self.handle_add(params),
-            Command::List => self.handle_list(),
+            Command::List(params) => self.handle_list(params),
+            Command::Search(params) => self.handle_search(params),
             Command::AddChild(params) => self.handle_add_child(params),
+            Command::RemoveChild(params) => self.handle_remove_child(params),
+            Command::SetQuantity(params) => self.handle_set_quantity(params),
+            Command::Delete(params) => self.handle_delete(params),
             Command::Tree(params) => self.handle_tree(params),
             Command::WhereUsed(params) => self.handle_where_used(params),
             Command::Stock(params) => self.handle_stock(params),
-            Command::Exit | Command::Help => {}
+            Command::SetMaturity(params) => self.handle_set_maturity(params),
+            Command::Revise(params) => self.handle_revise(params),
+            Command::Revisions(params) => self.handle_revisions(params),
+            Command::Check => self.handle_check(),
+            Command::Run(params) => return self.handle_run(params),
+            Command::ImportCsv(params) => self.handle_import_csv(params),
+            Command::ExportBom(params) => self.handle_export_bom(params),
+            Command::ExportGraph(params) => self.handle_export_graph(params),
+            Command::PnScheme(params) => self.handle_pn_scheme(params),
+            Command::SetAttr(params) => self.handle_set_attr(params),
+            Command::GetAttr(params) => self.handle_get_attr(params),
+            Command::RemoveAttr(params) => self.handle_remove_attr(params),
+            Command::Unit(params) => self.handle_unit(params),
+            Command::Exit | Command::Help => Ok(()),
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Run all the commands of a script, one per line, ignoring blank lines
+    /// and comments starting with `#`
+    ///
+    /// Returns the number of failing lines
+    fn run_script(&mut self, script: impl BufRead, stop_on_error: bool) -> io::Result<usize> {
+        let mut errors = 0;
+        for (index, line) in script.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = match get_command(line) {
+                Ok(Command::Exit) => break,
+                Ok(Command::Help) => {
+                    println!("PBS CLI commands: {}", COMMANDS);
+                    Ok(())
+                }
+                Ok(command) => self.handle_cmd(command),
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(e) = result {
+                eprintln!("ERROR : line {} : {}", index + 1, e);
+                errors += 1;
+                if stop_on_error {
+                    break;
+                }
+            }
         }
+        Ok(errors)
+    }
+
+    fn handle_create(&mut self, params: CreateParams) -> Result<()> {
+        let item = self
+            .store
+            .create_in(params.category.as_deref(), &params.name)?;
+        println!("  created {item}");
+        Ok(())
+    }
+
+    fn handle_add(&mut self, params: AddParams) -> Result<()> {
+        let item = self.store.new_item(&params.pn, &params.name)?;
+        println!("  added {item}");
+        Ok(())
+    }
+
+    fn handle_list(&self, params: ListParams) -> Result<()> {
+        let items = self.store.get_items()?;
+        self.print_items(items, params.output)
     }
 
-    fn handle_create(&mut self, params: CreateParams) {
-        match self.store.create(&params.name) {
-            Ok(item) => println!("  created {item}"),
-            Err(e) => eprintln!("ERROR : {:?}", e),
+    fn handle_search(&self, params: SearchParams) -> Result<()> {
+        let items = self.store.search(&params.query)?;
+        self.print_items(items, params.output)
+    }
+
+    fn handle_add_child(&mut self, params: AddChildParams) -> Result<()> {
+        match params.unit {
+            Some(unit) => {
+                self.store
+                    .add_child_in(&params.parent_pn, &params.child_pn, params.quantity, unit)
+            }
+            None => self
+                .store
+                .add_child(&params.parent_pn, &params.child_pn, params.quantity),
         }
     }
 
-    fn handle_add(&mut self, params: AddParams) {
-        match self.store.new_item(&params.pn, &params.name) {
-            Ok(item) => println!("  added {item}"),
-            Err(e) => eprintln!("ERROR : {:?}", e),
+    fn handle_remove_child(&mut self, params: RemoveChildParams) -> Result<()> {
+        self.store.remove_child(&params.parent_pn, &params.child_pn)
+    }
+
+    fn handle_set_quantity(&mut self, params: SetQuantityParams) -> Result<()> {
+        match params.unit {
+            Some(unit) => self.store.set_child_quantity_in(
+                &params.parent_pn,
+                &params.child_pn,
+                params.quantity,
+                unit,
+            ),
+            None => {
+                self.store
+                    .set_child_quantity(&params.parent_pn, &params.child_pn, params.quantity)
+            }
         }
     }
 
-    fn handle_list(&self) {
-        match self.store.get_items() {
-            Ok(items) => {
-                for item in items {
-                    println!("  - {item}");
+    fn handle_delete(&mut self, params: DeleteParams) -> Result<()> {
+        self.store.delete_item(&params.pn, params.force)?;
+        println!("  deleted {}", params.pn);
+        Ok(())
+    }
+
+    fn handle_tree(&self, params: TreeParams) -> Result<()> {
+        let tree = self.store.get_tree(&params.pn, params.depth)?;
+        match params.output {
+            OutputFormat::Text => {}
+            OutputFormat::Json => {
+                println!("{}", output::json(&tree));
+                return Ok(());
+            }
+            OutputFormat::Csv => {
+                print!("{}", output::csv(tree.iter().map(TreeRecord::from)));
+                return Ok(());
+            }
+        }
+        println!("  0 {}", tree.item);
+        for node in tree.iter().skip(1) {
+            println!(
+                "  {level} {indent}{item} : {quantity} ({total_quantity})",
+                level = node.level,
+                indent = "  ".repeat(node.level),
+                item = node.item,
+                quantity = node.quantity,
+                total_quantity = Quantity::new(node.total_quantity, node.item.unit())
+            );
+        }
+        Ok(())
+    }
+
+    fn handle_where_used(&self, params: WhereUsedParams) -> Result<()> {
+        if params.all {
+            return self.handle_where_used_all(params);
+        }
+        let items = self.store.where_used(&params.pn)?;
+        self.print_items(items, params.output)
+    }
+
+    fn handle_where_used_all(&self, params: WhereUsedParams) -> Result<()> {
+        let paths = self.store.where_used_all(&params.pn)?;
+        match params.output {
+            OutputFormat::Text => {}
+            OutputFormat::Json => {
+                println!("{}", output::json(&paths));
+                return Ok(());
+            }
+            OutputFormat::Csv => {
+                print!("{}", output::csv(paths.iter().map(UsageRecord::from)));
+                return Ok(());
+            }
+        }
+        for path in paths {
+            let pns = path.items.iter().map(|i| i.pn()).collect::<Vec<_>>();
+            println!(
+                "  - {root} : {quantity}  [{path}]",
+                root = path.root(),
+                quantity = Quantity::new(path.quantity, path.items[0].unit()),
+                path = pns.join(" < ")
+            );
+        }
+        Ok(())
+    }
+
+    fn handle_stock(&self, params: StockParams) -> Result<()> {
+        let stock = if params.leaves_only {
+            self.store.get_leaf_stock(&params.pn)?
+        } else {
+            self.store.get_stock(&params.pn)?
+        };
+        let mut lines = stock
+            .into_iter()
+            .map(ItemQuantity::from)
+            .collect::<Vec<_>>();
+        lines.sort_by(|a, b| a.item.pn().cmp(b.item.pn()));
+        match params.output {
+            OutputFormat::Text => {
+                for line in lines {
+                    println!("  - {} : {}", line.item, line.quantity);
                 }
             }
-            Err(e) => eprintln!("ERROR : {:?}", e),
+            OutputFormat::Json => println!("{}", output::json(&lines)),
+            OutputFormat::Csv => print!("{}", output::csv(&lines)),
+        }
+        Ok(())
+    }
+
+    fn handle_set_maturity(&mut self, params: SetMaturityParams) -> Result<()> {
+        let item = self.store.set_maturity(&params.pn, params.maturity)?;
+        println!("  updated {item}");
+        Ok(())
+    }
+
+    fn handle_revise(&mut self, params: ReviseParams) -> Result<()> {
+        let item = self.store.revise(&params.pn)?;
+        println!("  created {item}");
+        Ok(())
+    }
+
+    fn handle_revisions(&self, params: RevisionsParams) -> Result<()> {
+        for item in self.store.get_revisions(&params.pn)? {
+            println!("  - {item}");
+        }
+        Ok(())
+    }
+
+    /// Run the script file of a `run` command
+    ///
+    /// With `--transaction`, the script stops on the first failing line and
+    /// all its changes are cancelled.
+    ///
+    /// A script can't run itself, directly or through other scripts.
+    ///
+    /// Returns the number of failing lines
+    fn run_file(&mut self, params: &RunParams) -> std::result::Result<usize, String> {
+        let open_error = |e: io::Error| format!("can't open {} : {}", params.path, e);
+        let path = std::fs::canonicalize(&params.path).map_err(open_error)?;
+        if self.running.contains(&path) {
+            return Err(format!(
+                "{} is already running, a script can't run itself",
+                params.path
+            ));
+        }
+        let file = File::open(&path).map_err(open_error)?;
+        self.running.push(path);
+        let result = self.run_opened_file(BufReader::new(file), params);
+        self.running.pop();
+        result
+    }
+
+    fn run_opened_file(
+        &mut self,
+        script: impl BufRead,
+        params: &RunParams,
+    ) -> std::result::Result<usize, String> {
+        if !params.transaction {
+            return self
+                .run_script(script, params.stop_on_error)
+                .map_err(|e| format!("can't read {} : {}", params.path, e));
+        }
+        self.store.begin_transaction().map_err(|e| e.to_string())?;
+        let errors = self.run_script(script, true);
+        if let Ok(0) = errors {
+            self.store.commit_transaction().map_err(|e| e.to_string())?;
+        } else {
+            self.store
+                .rollback_transaction()
+                .map_err(|e| e.to_string())?;
+            eprintln!("ERROR : {} : all the changes are cancelled", params.path);
         }
+        errors.map_err(|e| format!("can't read {} : {}", params.path, e))
     }
 
-    fn handle_add_child(&mut self, params: AddChildParams) {
-        if let Err(e) = self
+    /// Run a script file, failing if one of its lines fails so a calling
+    /// script sees it as a failing line
+    fn handle_run(&mut self, params: RunParams) -> std::result::Result<(), String> {
+        match self.run_file(&params)? {
+            0 => Ok(()),
+            errors => Err(format!("{} : {errors} failing line(s)", params.path)),
+        }
+    }
+
+    fn handle_import_csv(&mut self, params: ImportCsvParams) -> Result<()> {
+        let open = |path: &str| {
+            File::open(path).map_err(|error| Error::FileErr {
+                path: path.to_string(),
+                error,
+            })
+        };
+        let items = params.items.as_deref().map(open).transpose()?;
+        let links = params.links.as_deref().map(open).transpose()?;
+        let result = self
             .store
-            .add_child(&params.parent_pn, &params.child_pn, params.quantity)
-        {
-            eprintln!("ERROR: {:?}", e);
+            .import_csv(items, links, &params.columns, params.dry_run);
+        if let Ok(report) | Err(Error::ImportFailed(report)) = &result {
+            print_import_report(report);
         }
+        if result?.dry_run {
+            println!("  dry run, nothing was imported");
+        }
+        Ok(())
+    }
+
+    fn handle_export_bom(&mut self, params: ExportBomParams) -> Result<()> {
+        // the BOM is built before writing the file, which is left untouched
+        // if the export fails
+        let mut bom = Cursor::new(Vec::new());
+        if params.path.ends_with(".xlsx") {
+            self.store.export_bom_xlsx(&params.pn, &mut bom)?;
+        } else {
+            self.store.export_bom_csv(&params.pn, &mut bom)?;
+        }
+        std::fs::write(&params.path, bom.into_inner()).map_err(|error| Error::FileErr {
+            path: params.path.clone(),
+            error,
+        })?;
+        println!("  exported {}", params.path);
+        Ok(())
+    }
+
+    fn handle_export_graph(&mut self, params: ExportGraphParams) -> Result<()> {
+        let format = if params.path.ends_with(".mmd") {
+            GraphFormat::Mermaid
+        } else {
+            GraphFormat::Dot
+        };
+        let graph = self.store.export_graph(&params.pn, format)?;
+        std::fs::write(&params.path, graph).map_err(|error| Error::FileErr {
+            path: params.path.clone(),
+            error,
+        })?;
+        println!("  exported {}", params.path);
+        Ok(())
     }
 
-    fn handle_tree(&self, params: TreeParams) {
-        match self.store.get_children(&params.pn) {
-            Ok(children) => {
-                for (item, quantity) in children {
-                    println!("  - {item} : {quantity}");
+    fn handle_pn_scheme(&mut self, params: PnSchemeParams) -> Result<()> {
+        if let Some(scheme) = &params.scheme {
+            self.store.set_pn_scheme(scheme)?;
+        }
+        match self.store.pn_provider()? {
+            Some(provider) => println!("  PN scheme : {}", provider.name()),
+            None => println!("  PN scheme : simple (default, PNs added manually aren't checked)"),
+        }
+        Ok(())
+    }
+
+    fn handle_unit(&mut self, params: UnitParams) -> Result<()> {
+        if let Some(unit) = params.unit {
+            self.store.set_unit(&params.pn, unit)?;
+        }
+        let item = self.store.get_item_by_pn(&params.pn)?;
+        println!("  {} : {}", item, item.unit());
+        Ok(())
+    }
+
+    fn handle_set_attr(&mut self, params: SetAttrParams) -> Result<()> {
+        let value = AttributeValue::parse(params.kind, &params.value)?;
+        self.store.set_attribute(&params.pn, &params.key, &value)?;
+        println!("  {} : {} = {value}", params.pn, params.key);
+        Ok(())
+    }
+
+    fn handle_get_attr(&self, params: GetAttrParams) -> Result<()> {
+        let item = self.store.get_item_by_pn(&params.pn)?;
+        let mut attributes = self.store.get_attributes(&item)?;
+        if let Some(key) = &params.key {
+            let value = attributes
+                .remove(key)
+                .ok_or_else(|| Error::AttributeNotFound {
+                    pn: params.pn.clone(),
+                    key: key.clone(),
+                })?;
+            attributes = Attributes::from([(key.clone(), value)]);
+        }
+        match params.output {
+            OutputFormat::Text => {
+                for (key, value) in &attributes {
+                    println!("  - {key} : {value} ({})", value.kind());
                 }
             }
-            Err(e) => eprintln!("ERROR : {:?}", e),
+            OutputFormat::Json => println!("{}", output::json(&attributes)),
+            OutputFormat::Csv => print!(
+                "{}",
+                output::csv(attributes.iter().map(AttributeRecord::from))
+            ),
         }
+        Ok(())
     }
-    fn handle_where_used(&self, params: WhereUsedParams) {
-        match self.store.where_used(&params.pn) {
-            Ok(parents) => {
-                for item in parents {
+
+    fn handle_remove_attr(&mut self, params: RemoveAttrParams) -> Result<()> {
+        self.store.remove_attribute(&params.pn, &params.key)?;
+        println!("  removed {} from {}", params.key, params.pn);
+        Ok(())
+    }
+
+    /// Print items with their attributes
+    fn print_items(&self, items: Vec<Item>, format: OutputFormat) -> Result<()> {
+        let items = items
+            .into_iter()
+            .map(|item| {
+                let attributes = self.store.get_attributes(&item)?;
+                Ok((item, attributes))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match format {
+            OutputFormat::Text => {
+                for (item, attributes) in &items {
                     println!("  - {item}");
+                    for (key, value) in attributes {
+                        println!("      {key} : {value}");
+                    }
                 }
             }
-            Err(e) => eprintln!("ERROR : {:?}", e),
+            OutputFormat::Json => {
+                let records = items.iter().map(ItemRecord::from).collect::<Vec<_>>();
+                println!("{}", output::json(&records));
+            }
+            OutputFormat::Csv => print!("{}", output::items_csv(&items)),
         }
+        Ok(())
     }
 
-    fn handle_stock(&self, params: StockParams) {
-        match self.store.get_stock(&params.pn) {
-            Ok(items) => {
-                for (item, quantity) in items {
-                    println!("  - {item} : {quantity}");
-                }
-            }
-            Err(e) => eprintln!("ERROR : {:?}", e),
+    fn handle_check(&self) -> Result<()> {
+        let cycles = self.store.find_cycles()?;
+        if cycles.is_empty() {
+            println!("  no cycle found");
+        }
+        for cycle in cycles {
+            let path = cycle.iter().map(|i| i.pn()).collect::<Vec<_>>();
+            println!("  - cycle : {}", path.join(" -> "));
         }
+        Ok(())
     }
 }
 
-fn main() -> Result<()> {
-    let mut pbs_cli = PbsCli::new()?;
+/// Print the changes and the failing rows of a CSV import
+fn print_import_report(report: &ImportReport) {
+    for (line, change) in report.changes() {
+        println!("  - {} line {} : {}", line.table, line.line, change);
+    }
+    for line in report.errors() {
+        if let Err(e) = &line.result {
+            eprintln!("ERROR : {} line {} : {}", line.table, line.line, e);
+        }
+    }
+    let unchanged = report.lines.len() - report.changes().count() - report.errors().count();
+    if unchanged > 0 {
+        println!("  {unchanged} row(s) already up to date");
+    }
+}
+
+fn repl(pbs_cli: &mut PbsCli) {
     loop {
         match pbs_cli.prompt() {
+            Ok(input) if input.is_empty() => break,
             Ok(input) => match get_command(&input) {
                 Ok(cmd) => match cmd {
                     Command::Exit => break,
                     Command::Help => println!("PBS CLI commands: {}", COMMANDS),
-                    command => pbs_cli.handle_cmd(command),
+                    command => {
+                        if let Err(err) = pbs_cli.handle_cmd(command) {
+                            eprintln!("ERROR : {}", err);
+                        }
+                    }
                 },
                 Err(err) => eprintln!("ERROR : {}", err),
             },
             Err(err) => eprintln!("ERROR : {}", err),
         }
     }
-    Ok(())
+}
+
+const USAGE: &str = r#"usage : pbs_cli [--store <FILE>] [<COMMAND>]
+
+Without command, the commands are read from stdin : interactively from a
+terminal, or as a script when stdin is redirected.
+
+options :
+ - --store <FILE>                                 Use the store <FILE> instead of `store.db3`
+ - --help                                         This help"#;
+
+/// Exit code of a command line which can't be parsed
+const USAGE_ERROR: u8 = 2;
+
+/// Command line options
+struct Options {
+    store: String,
+    help: bool,
+    /// The command and its params, if any
+    command: Vec<String>,
+}
+
+impl Options {
+    /// Parse the command line arguments, which are options followed by an
+    /// optional command
+    fn parse(args: impl Iterator<Item = String>) -> std::result::Result<Self, String> {
+        let mut options = Options {
+            store: STORE_URI.to_string(),
+            help: false,
+            command: Vec::new(),
+        };
+        let mut args = args.peekable();
+        while let Some(arg) = args.next_if(|arg| arg.starts_with("--")) {
+            match arg.as_str() {
+                "--store" => {
+                    options.store = args.next().ok_or("missing <FILE> after --store")?;
+                }
+                "--help" => options.help = true,
+                _ => match arg.strip_prefix("--store=") {
+                    Some(store) => options.store = store.to_string(),
+                    None => return Err(format!("unknown option {arg}")),
+                },
+            }
+        }
+        options.command = args.collect();
+        Ok(options)
+    }
+
+    /// The command, as it would be typed in the interactive CLI
+    fn command_line(&self) -> String {
+        self.command
+            .iter()
+            .map(|arg| {
+                if arg.is_empty() || arg.contains(char::is_whitespace) {
+                    format!("\"{arg}\"")
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Run a single command given on the command line
+fn run_command(pbs_cli: &mut PbsCli, input: &str) -> ExitCode {
+    let result = match get_command(input) {
+        Ok(Command::Exit) => Ok(()),
+        Ok(Command::Help) => {
+            println!("PBS CLI commands: {}", COMMANDS);
+            Ok(())
+        }
+        Ok(command) => pbs_cli.handle_cmd(command),
+        Err(_) => {
+            eprintln!("ERROR : invalid command `{input}`, see `pbs_cli help`");
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("ERROR : {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run the script piped to stdin
+fn run_stdin(pbs_cli: &mut PbsCli) -> ExitCode {
+    match pbs_cli.run_script(io::stdin().lock(), false) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("ERROR : {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("ERROR : {e}\n{USAGE}");
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+    if options.help {
+        println!("{USAGE}\n\nPBS CLI commands: {COMMANDS}");
+        return ExitCode::SUCCESS;
+    }
+    let mut pbs_cli = match PbsCli::new(&options.store) {
+        Ok(pbs_cli) => pbs_cli,
+        Err(e) => {
+            eprintln!("ERROR : can't open the store {} : {}", options.store, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if !options.command.is_empty() {
+        run_command(&mut pbs_cli, &options.command_line())
+    } else if io::stdin().is_terminal() {
+        repl(&mut pbs_cli);
+        ExitCode::SUCCESS
+    } else {
+        run_stdin(&mut pbs_cli)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_options() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(STORE_URI, options.store);
+        assert!(options.command.is_empty());
+
+        let options = parse(&["--store", "dir/my.db3", "delete", "PN", "--force"]).unwrap();
+        assert_eq!("dir/my.db3", options.store);
+        assert_eq!("delete PN --force", options.command_line());
+
+        let options = parse(&["--store=my.db3", "add", "PN", "my name"]).unwrap();
+        assert_eq!("my.db3", options.store);
+        assert_eq!("add PN \"my name\"", options.command_line());
+
+        assert!(parse(&["--help"]).unwrap().help);
+        assert!(parse(&["--store"]).is_err());
+        assert!(parse(&["--unknown", "list"]).is_err());
+    }
+
+    /// Write the scripts in a new temporary directory, and return it
+    fn scripts(name: &str, scripts: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pbs_cli_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, script) in scripts {
+            std::fs::write(dir.join(file), script).unwrap();
+        }
+        dir
+    }
+
+    fn run(
+        pbs_cli: &mut PbsCli,
+        path: PathBuf,
+        transaction: bool,
+    ) -> std::result::Result<(), String> {
+        pbs_cli.handle_run(RunParams {
+            path: path.to_string_lossy().into_owned(),
+            stop_on_error: false,
+            transaction,
+        })
+    }
+
+    #[test]
+    fn test_nested_script_failure() {
+        let bad = "add 2 table\nadd 2 table\n";
+        let dir = scripts("nested", &[("bad.txt", bad)]);
+        let outer = format!("add 1 chair\nrun {}\n", dir.join("bad.txt").display());
+        std::fs::write(dir.join("outer.txt"), outer).unwrap();
+
+        let mut pbs_cli = PbsCli::new(":memory:").unwrap();
+        let err = run(&mut pbs_cli, dir.join("outer.txt"), true).unwrap_err();
+        assert!(err.ends_with("outer.txt : 1 failing line(s)"), "{err}");
+        // the whole outer script is cancelled
+        assert!(pbs_cli.store.get_items().unwrap().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_recursive_script() {
+        let dir = scripts("recursive", &[]);
+        let script = format!("add 1 chair\nrun {}\n", dir.join("self.txt").display());
+        std::fs::write(dir.join("self.txt"), script).unwrap();
+
+        let mut pbs_cli = PbsCli::new(":memory:").unwrap();
+        assert!(run(&mut pbs_cli, dir.join("self.txt"), false).is_err());
+        assert_eq!(1, pbs_cli.store.get_items().unwrap().len());
+        assert!(pbs_cli.running.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,166 @@
+//! Machine readable outputs of the commands, selected with
+//! `--output json` or `--output csv`
+
+use std::collections::BTreeSet;
+
+use pbs_core::{
+    AttributeType, AttributeValue, Attributes, Item, ItemMaturity, TreeNode, Unit, UsagePath,
+};
+use serde::Serialize;
+
+/// Serialize `value` as pretty printed JSON
+pub fn json<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("pbs values are serializable to JSON")
+}
+
+/// Serialize `records` as CSV, with a header line named after the fields of
+/// the records
+///
+/// The records must be flat structures : use [TreeRecord] or
+/// [UsageRecord] for the nested ones.
+pub fn csv<T: Serialize>(records: impl IntoIterator<Item = T>) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer
+            .serialize(record)
+            .expect("pbs records are serializable to CSV");
+    }
+    let bytes = writer.into_inner().expect("writing to memory can't fail");
+    String::from_utf8(bytes).expect("pbs records are valid UTF-8")
+}
+
+/// An item with its attributes, as a JSON object
+#[derive(Serialize)]
+pub struct ItemRecord<'a> {
+    #[serde(flatten)]
+    item: &'a Item,
+    attributes: &'a Attributes,
+}
+
+impl<'a> From<&'a (Item, Attributes)> for ItemRecord<'a> {
+    fn from((item, attributes): &'a (Item, Attributes)) -> Self {
+        ItemRecord { item, attributes }
+    }
+}
+
+/// Write items with their attributes as CSV, with one column per attribute
+/// key after the columns of the items
+pub fn items_csv(items: &[(Item, Attributes)]) -> String {
+    let keys = items
+        .iter()
+        .flat_map(|(_, attributes)| attributes.keys())
+        .collect::<BTreeSet<_>>();
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let header = ["pn", "version", "name", "maturity", "unit"];
+    let header = header.into_iter().chain(keys.iter().map(|k| k.as_str()));
+    writer
+        .write_record(header)
+        .expect("writing to memory can't fail");
+    for (item, attributes) in items {
+        let values = keys
+            .iter()
+            .map(|key| {
+                attributes
+                    .get(*key)
+                    .map(|value| value.to_string())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let record = (
+            item.pn(),
+            item.version(),
+            item.name(),
+            item.maturity(),
+            item.unit(),
+            values,
+        );
+        writer
+            .serialize(record)
+            .expect("pbs records are serializable to CSV");
+    }
+    let bytes = writer.into_inner().expect("writing to memory can't fail");
+    String::from_utf8(bytes).expect("pbs records are valid UTF-8")
+}
+
+/// An attribute of an item, as a CSV record
+#[derive(Serialize)]
+pub struct AttributeRecord<'a> {
+    key: &'a str,
+    #[serde(rename = "type")]
+    kind: AttributeType,
+    value: String,
+}
+
+impl<'a> From<(&'a String, &'a AttributeValue)> for AttributeRecord<'a> {
+    fn from((key, value): (&'a String, &'a AttributeValue)) -> Self {
+        AttributeRecord {
+            key,
+            kind: value.kind(),
+            value: value.to_string(),
+        }
+    }
+}
+
+/// A node of a tree, as a CSV record
+#[derive(Serialize)]
+pub struct TreeRecord<'a> {
+    level: usize,
+    pn: &'a str,
+    version: usize,
+    name: &'a str,
+    maturity: ItemMaturity,
+    quantity: f64,
+    quantity_unit: Unit,
+    total_quantity: f64,
+    unit: Unit,
+}
+
+impl<'a> From<&'a TreeNode> for TreeRecord<'a> {
+    fn from(node: &'a TreeNode) -> Self {
+        TreeRecord {
+            level: node.level,
+            pn: node.item.pn(),
+            version: node.item.version(),
+            name: node.item.name(),
+            maturity: node.item.maturity(),
+            quantity: node.quantity.value,
+            quantity_unit: node.quantity.unit,
+            total_quantity: node.total_quantity,
+            unit: node.item.unit(),
+        }
+    }
+}
+
+/// A where-used path, as a CSV record : the top level product, the quantity
+/// of the used item in it, in its unit, and the PNs of the path separated
+/// with ` < `
+#[derive(Serialize)]
+pub struct UsageRecord<'a> {
+    pn: &'a str,
+    version: usize,
+    name: &'a str,
+    maturity: ItemMaturity,
+    quantity: f64,
+    unit: Unit,
+    path: String,
+}
+
+impl<'a> From<&'a UsagePath> for UsageRecord<'a> {
+    fn from(path: &'a UsagePath) -> Self {
+        let root = path.root();
+        UsageRecord {
+            pn: root.pn(),
+            version: root.version(),
+            name: root.name(),
+            maturity: root.maturity(),
+            quantity: path.quantity,
+            unit: path.items[0].unit(),
+            path: path
+                .items
+                .iter()
+                .map(|item| item.pn())
+                .collect::<Vec<_>>()
+                .join(" < "),
+        }
+    }
+}
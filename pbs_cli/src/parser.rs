@@ -1,23 +1,44 @@
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_till, take_while1},
+    bytes::complete::{tag, take_till, take_till1, take_while1},
     character::complete::{char, digit1, multispace0, space0, space1},
-    combinator::{eof, map_res},
+    combinator::{eof, map, map_res, opt, value, verify},
     error::ParseError,
-    sequence::{delimited, pair, preceded, tuple},
+    multi::{many0, many1, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, tuple},
     AsChar, Compare, IResult, InputLength, InputTake, InputTakeAtPosition, Parser,
 };
+use pbs_core::{
+    parse_link_quantity, AttributeType, ImportColumns, ItemMaturity, SearchQuery, Unit,
+};
 
 #[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(test, derive(PartialEq))]
 pub enum Command {
     Create(CreateParams),
     Add(AddParams),
     AddChild(AddChildParams),
-    List,
+    RemoveChild(RemoveChildParams),
+    SetQuantity(SetQuantityParams),
+    Delete(DeleteParams),
+    List(ListParams),
+    Search(SearchParams),
     Tree(TreeParams),
     WhereUsed(WhereUsedParams),
     Stock(StockParams),
+    SetMaturity(SetMaturityParams),
+    Revise(ReviseParams),
+    Revisions(RevisionsParams),
+    Check,
+    Run(RunParams),
+    ImportCsv(ImportCsvParams),
+    ExportBom(ExportBomParams),
+    ExportGraph(ExportGraphParams),
+    PnScheme(PnSchemeParams),
+    SetAttr(SetAttrParams),
+    GetAttr(GetAttrParams),
+    RemoveAttr(RemoveAttrParams),
+    Unit(UnitParams),
     Help,
     Exit,
 }
@@ -59,12 +80,14 @@ impl<I, O> CommandWithoutParamsResult<I, O> for IResult<I, O> {
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CreateParams {
     pub name: String,
+    pub category: Option<String>,
 }
 
-impl From<&str> for CreateParams {
-    fn from(value: &str) -> Self {
+impl From<(&str, Option<&str>)> for CreateParams {
+    fn from(value: (&str, Option<&str>)) -> Self {
         CreateParams {
-            name: value.to_string(),
+            name: value.0.to_string(),
+            category: value.1.map(String::from),
         }
     }
 }
@@ -99,19 +122,22 @@ impl ParamsCmd for AddParams {
 
 /// Params for the `add-child` command
 #[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(test, derive(PartialEq))]
 pub struct AddChildParams {
     pub parent_pn: String,
     pub child_pn: String,
-    pub quantity: usize,
+    pub quantity: f64,
+    /// Unit of the quantity, the unit of the child if not given
+    pub unit: Option<Unit>,
 }
 
-impl From<(&str, &str, usize)> for AddChildParams {
-    fn from(value: (&str, &str, usize)) -> Self {
+impl From<(&str, &str, (f64, Option<Unit>))> for AddChildParams {
+    fn from(value: (&str, &str, (f64, Option<Unit>))) -> Self {
         AddChildParams {
             parent_pn: value.0.to_string(),
             child_pn: value.1.to_string(),
-            quantity: value.2,
+            quantity: value.2 .0,
+            unit: value.2 .1,
         }
     }
 }
@@ -122,17 +148,174 @@ impl ParamsCmd for AddChildParams {
     }
 }
 
+/// Params for the `remove-child` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct RemoveChildParams {
+    pub parent_pn: String,
+    pub child_pn: String,
+}
+
+impl From<(&str, &str)> for RemoveChildParams {
+    fn from(value: (&str, &str)) -> Self {
+        RemoveChildParams {
+            parent_pn: value.0.to_string(),
+            child_pn: value.1.to_string(),
+        }
+    }
+}
+
+impl ParamsCmd for RemoveChildParams {
+    fn cmd(self) -> Command {
+        Command::RemoveChild(self)
+    }
+}
+
+/// Params for the `set-qty` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct SetQuantityParams {
+    pub parent_pn: String,
+    pub child_pn: String,
+    pub quantity: f64,
+    /// New unit of the link, which keeps its unit if not given
+    pub unit: Option<Unit>,
+}
+
+impl From<(&str, &str, (f64, Option<Unit>))> for SetQuantityParams {
+    fn from(value: (&str, &str, (f64, Option<Unit>))) -> Self {
+        SetQuantityParams {
+            parent_pn: value.0.to_string(),
+            child_pn: value.1.to_string(),
+            quantity: value.2 .0,
+            unit: value.2 .1,
+        }
+    }
+}
+
+impl ParamsCmd for SetQuantityParams {
+    fn cmd(self) -> Command {
+        Command::SetQuantity(self)
+    }
+}
+
+/// Params for the `delete` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct DeleteParams {
+    pub pn: String,
+    pub force: bool,
+}
+
+impl From<(&str, Option<&str>)> for DeleteParams {
+    fn from(value: (&str, Option<&str>)) -> Self {
+        DeleteParams {
+            pn: value.0.to_string(),
+            force: value.1.is_some(),
+        }
+    }
+}
+
+impl ParamsCmd for DeleteParams {
+    fn cmd(self) -> Command {
+        Command::Delete(self)
+    }
+}
+
+/// Format of the output of the commands showing items
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+/// Params for the `list` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct ListParams {
+    pub output: OutputFormat,
+}
+
+impl From<Option<OutputFormat>> for ListParams {
+    fn from(value: Option<OutputFormat>) -> Self {
+        ListParams {
+            output: value.unwrap_or_default(),
+        }
+    }
+}
+
+impl ParamsCmd for ListParams {
+    fn cmd(self) -> Command {
+        Command::List(self)
+    }
+}
+
+/// Params for the `search` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct SearchParams {
+    pub query: SearchQuery,
+    pub output: OutputFormat,
+}
+
+impl
+    From<(
+        &str,
+        Option<ItemMaturity>,
+        Option<usize>,
+        Vec<(&str, &str)>,
+        Option<OutputFormat>,
+    )> for SearchParams
+{
+    fn from(
+        value: (
+            &str,
+            Option<ItemMaturity>,
+            Option<usize>,
+            Vec<(&str, &str)>,
+            Option<OutputFormat>,
+        ),
+    ) -> Self {
+        SearchParams {
+            query: SearchQuery {
+                pattern: value.0.to_string(),
+                maturity: value.1,
+                version: value.2,
+                attributes: value
+                    .3
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+            },
+            output: value.4.unwrap_or_default(),
+        }
+    }
+}
+
+impl ParamsCmd for SearchParams {
+    fn cmd(self) -> Command {
+        Command::Search(self)
+    }
+}
+
 /// Params for the `tree` command
 #[derive(Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct TreeParams {
     pub pn: String,
+    pub depth: Option<usize>,
+    pub output: OutputFormat,
 }
 
-impl From<&str> for TreeParams {
-    fn from(value: &str) -> Self {
+impl From<(&str, Option<usize>, Option<OutputFormat>)> for TreeParams {
+    fn from(value: (&str, Option<usize>, Option<OutputFormat>)) -> Self {
         TreeParams {
-            pn: value.to_string(),
+            pn: value.0.to_string(),
+            depth: value.1,
+            output: value.2.unwrap_or_default(),
         }
     }
 }
@@ -148,12 +331,16 @@ impl ParamsCmd for TreeParams {
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct WhereUsedParams {
     pub pn: String,
+    pub all: bool,
+    pub output: OutputFormat,
 }
 
-impl From<&str> for WhereUsedParams {
-    fn from(value: &str) -> Self {
+impl From<(&str, Option<&str>, Option<OutputFormat>)> for WhereUsedParams {
+    fn from(value: (&str, Option<&str>, Option<OutputFormat>)) -> Self {
         WhereUsedParams {
-            pn: value.to_string(),
+            pn: value.0.to_string(),
+            all: value.1.is_some(),
+            output: value.2.unwrap_or_default(),
         }
     }
 }
@@ -169,12 +356,16 @@ impl ParamsCmd for WhereUsedParams {
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct StockParams {
     pub pn: String,
+    pub leaves_only: bool,
+    pub output: OutputFormat,
 }
 
-impl From<&str> for StockParams {
-    fn from(value: &str) -> Self {
+impl From<(&str, Option<&str>, Option<OutputFormat>)> for StockParams {
+    fn from(value: (&str, Option<&str>, Option<OutputFormat>)) -> Self {
         StockParams {
-            pn: value.to_string(),
+            pn: value.0.to_string(),
+            leaves_only: value.1.is_some(),
+            output: value.2.unwrap_or_default(),
         }
     }
 }
@@ -185,181 +376,895 @@ impl ParamsCmd for StockParams {
     }
 }
 
-// ====================================================================
-// parser helper functions
-// ====================================================================
-
-/// parser for a cmd followed with params.
-fn cmd<I, O, E: ParseError<I>, F, T>(cmd: T, parser: F) -> impl FnMut(I) -> IResult<I, O, E>
-where
-    I: InputTake + Compare<T>,
-    T: InputLength + Clone,
-    F: Parser<I, O, E>,
-{
-    preceded(tag(cmd), parser)
+/// Params for the `review`, `release` and `obsolete` commands
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct SetMaturityParams {
+    pub pn: String,
+    pub maturity: ItemMaturity,
 }
 
-/// parser for a whitespace separated param
-fn param<I, O, E: ParseError<I>, F>(mut parser: F) -> impl FnMut(I) -> IResult<I, O, E>
-where
-    I: InputTakeAtPosition,
-    <I as InputTakeAtPosition>::Item: AsChar + Clone,
-    F: Parser<I, O, E>,
-{
-    move |input: I| {
-        let (input, _) = space1(input)?;
-        parser.parse(input)
+impl From<(&str, ItemMaturity)> for SetMaturityParams {
+    fn from(value: (&str, ItemMaturity)) -> Self {
+        SetMaturityParams {
+            pn: value.0.to_string(),
+            maturity: value.1,
+        }
     }
 }
 
-/// A PN is alphanum and can contain `.`, `-` or `_`
-fn pn(input: &str) -> IResult<&str, &str> {
-    take_while1(|c: char| c.is_alphanumeric() || ".-_".find(c).is_some())(input)
+impl ParamsCmd for SetMaturityParams {
+    fn cmd(self) -> Command {
+        Command::SetMaturity(self)
+    }
 }
 
-#[allow(rustdoc::invalid_html_tags)]
-/// get the <name> param, allowing direct
-///
-/// A name can start and end with `"`
-fn name(input: &str) -> IResult<&str, &str> {
-    alt((
-        delimited(char('"'), take_till(|c| c == '"'), char('"')),
-        take_while1(|c: char| c.is_alphanumeric() || ".-_".find(c).is_some()),
-    ))(input)
+/// Params for the `revise` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct ReviseParams {
+    pub pn: String,
 }
 
-/// Parser for a number
-fn quantity(input: &str) -> IResult<&str, usize> {
-    map_res(digit1, |s: &str| s.parse::<usize>())(input)
+impl From<&str> for ReviseParams {
+    fn from(value: &str) -> Self {
+        ReviseParams {
+            pn: value.to_string(),
+        }
+    }
 }
 
-/// End of line parser
-fn eol(input: &str) -> IResult<&str, ()> {
-    pair(multispace0, eof)(input).map(|(i, (_, _))| (i, ()))
+impl ParamsCmd for ReviseParams {
+    fn cmd(self) -> Command {
+        Command::Revise(self)
+    }
 }
 
-// ====================================================================
-// command parsers
-// ====================================================================
+/// Params for the `revisions` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct RevisionsParams {
+    pub pn: String,
+}
 
-/// `create <name>`
-fn cmd_create(input: &str) -> IResult<&str, Command> {
-    let params = param(pn);
-    cmd("create", params)(input).cmd_n::<CreateParams>()
+impl From<&str> for RevisionsParams {
+    fn from(value: &str) -> Self {
+        RevisionsParams {
+            pn: value.to_string(),
+        }
+    }
 }
 
-/// `add <pn> <name>`
-fn cmd_add(input: &str) -> IResult<&str, Command> {
-    let params = pair(param(pn), param(name));
-    cmd("add", params)(input).cmd_n::<AddParams>()
+impl ParamsCmd for RevisionsParams {
+    fn cmd(self) -> Command {
+        Command::Revisions(self)
+    }
 }
 
-/// `list`
-fn cmd_list(input: &str) -> IResult<&str, Command> {
-    tag("list")(input).cmd_0(Command::List)
+/// Params for the `run` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct RunParams {
+    pub path: String,
+    pub stop_on_error: bool,
+    pub transaction: bool,
 }
 
-/// `exit`
-fn cmd_exit(input: &str) -> IResult<&str, Command> {
-    tag("exit")(input).cmd_0(Command::Exit)
+impl From<(&str, Vec<&str>)> for RunParams {
+    fn from(value: (&str, Vec<&str>)) -> Self {
+        RunParams {
+            path: value.0.to_string(),
+            stop_on_error: value.1.contains(&"--stop-on-error"),
+            transaction: value.1.contains(&"--transaction"),
+        }
+    }
 }
 
-/// `help`
-fn cmd_help(input: &str) -> IResult<&str, Command> {
-    tag("help")(input).cmd_0(Command::Help)
+impl ParamsCmd for RunParams {
+    fn cmd(self) -> Command {
+        Command::Run(self)
+    }
 }
 
-/// `tree <pn>`
-fn cmd_tree(input: &str) -> IResult<&str, Command> {
-    let params = param(pn);
-    cmd("tree", params)(input).cmd_n::<TreeParams>()
+/// A field of [ImportColumns], whose column can be renamed
+#[derive(Copy, Clone)]
+enum ImportField {
+    Pn,
+    Name,
+    ParentPn,
+    ChildPn,
+    Quantity,
 }
 
-/// `where-used <pn>`
-fn cmd_where_used(input: &str) -> IResult<&str, Command> {
-    let params = param(pn);
-    cmd("where-used", params)(input).cmd_n::<WhereUsedParams>()
+/// An option of the `import-csv` command
+#[derive(Clone)]
+enum ImportOption<'a> {
+    Items(&'a str),
+    Links(&'a str),
+    Columns(Vec<(ImportField, &'a str)>),
+    DryRun,
 }
 
-/// `add-child <parent-pn> <child-pn> <quantity>`
-fn cmd_add_child(input: &str) -> IResult<&str, Command> {
-    let params = tuple((param(pn), param(pn), param(quantity)));
-    cmd("add-child", params)(input).cmd_n::<AddChildParams>()
+/// Params for the `import-csv` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct ImportCsvParams {
+    /// Path of the items CSV file
+    pub items: Option<String>,
+    /// Path of the links CSV file
+    pub links: Option<String>,
+    pub columns: ImportColumns,
+    pub dry_run: bool,
 }
 
-/// `stock <pn>`
-fn cmd_stock(input: &str) -> IResult<&str, Command> {
-    let params = param(pn);
-    preceded(tag("stock"), params)(input).cmd_n::<StockParams>()
+impl From<Vec<ImportOption<'_>>> for ImportCsvParams {
+    fn from(value: Vec<ImportOption>) -> Self {
+        let mut params = ImportCsvParams {
+            items: None,
+            links: None,
+            columns: ImportColumns::default(),
+            dry_run: false,
+        };
+        for option in value {
+            match option {
+                ImportOption::Items(path) => params.items = Some(path.to_string()),
+                ImportOption::Links(path) => params.links = Some(path.to_string()),
+                ImportOption::Columns(mapping) => {
+                    for (field, column) in mapping {
+                        let column = column.to_string();
+                        match field {
+                            ImportField::Pn => params.columns.pn = column,
+                            ImportField::Name => params.columns.name = column,
+                            ImportField::ParentPn => params.columns.parent_pn = column,
+                            ImportField::ChildPn => params.columns.child_pn = column,
+                            ImportField::Quantity => params.columns.quantity = column,
+                        }
+                    }
+                }
+                ImportOption::DryRun => params.dry_run = true,
+            }
+        }
+        params
+    }
 }
 
-/// Get the command of the input
-pub fn get_command(input: &str) -> Result<Command, nom::Err<nom::error::Error<&str>>> {
-    delimited(
-        space0,
-        alt((
-            cmd_create,
-            cmd_add,
-            cmd_list,
-            cmd_add_child,
-            cmd_tree,
-            cmd_help,
-            cmd_exit,
-            cmd_where_used,
-            cmd_stock,
-        )),
-        eol,
-    )(input)
-    .map(|(_, cmd)| cmd)
+impl ParamsCmd for ImportCsvParams {
+    fn cmd(self) -> Command {
+        Command::ImportCsv(self)
+    }
 }
 
-/// =================================================================
-/// Test
-/// =================================================================
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Params for the `export-bom` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct ExportBomParams {
+    pub pn: String,
+    pub path: String,
+}
 
-    #[test]
-    fn test_pn() {
-        assert_eq!(pn("PN"), Ok(("", "PN")));
-        assert_eq!(pn("PN111"), Ok(("", "PN111")));
-        assert_eq!(pn("PN111AA"), Ok(("", "PN111AA")));
-        assert_eq!(pn("PN111-AA"), Ok(("", "PN111-AA")));
-        assert_eq!(pn("PN111_AA"), Ok(("", "PN111_AA")));
-        assert_eq!(pn("PN111.AA"), Ok(("", "PN111.AA")));
-        assert_eq!(pn("PN111$AA"), Ok(("$AA", "PN111")));
+impl From<(&str, &str)> for ExportBomParams {
+    fn from(value: (&str, &str)) -> Self {
+        ExportBomParams {
+            pn: value.0.to_string(),
+            path: value.1.to_string(),
+        }
     }
+}
 
-    #[test]
-    fn test_name() {
-        assert_eq!(name("NAME"), Ok(("", "NAME")));
-        assert_eq!(name("\"NAME\""), Ok(("", "NAME")));
-        assert_eq!(
-            name("\"NAME WITH - _ CHARS\""),
-            Ok(("", "NAME WITH - _ CHARS"))
-        );
+impl ParamsCmd for ExportBomParams {
+    fn cmd(self) -> Command {
+        Command::ExportBom(self)
     }
+}
 
-    #[test]
-    fn test_list_ok() {
-        assert_eq!(Command::List, get_command("list").unwrap());
-        assert_eq!(Command::List, get_command("  list").unwrap());
-        assert_eq!(Command::List, get_command("  \tlist \t ").unwrap());
-    }
+/// Params for the `export-graph` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct ExportGraphParams {
+    pub pn: String,
+    pub path: String,
+}
 
-    #[test]
-    fn test_exit_ok() {
-        assert_eq!(Command::Exit, get_command("exit").unwrap());
-        assert_eq!(Command::Exit, get_command("  exit").unwrap());
-        assert_eq!(Command::Exit, get_command("  exit  ").unwrap());
+impl From<(&str, &str)> for ExportGraphParams {
+    fn from(value: (&str, &str)) -> Self {
+        ExportGraphParams {
+            pn: value.0.to_string(),
+            path: value.1.to_string(),
+        }
     }
+}
 
-    #[test]
-    fn test_exit_err() {
-        assert!(get_command("exi").is_err());
-        assert!(get_command("exit4").is_err());
-        assert!(get_command("exit 4").is_err());
+impl ParamsCmd for ExportGraphParams {
+    fn cmd(self) -> Command {
+        Command::ExportGraph(self)
+    }
+}
+
+/// Params for the `pn-scheme` command, which shows the PN scheme of the
+/// store if no scheme is given
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct PnSchemeParams {
+    pub scheme: Option<String>,
+}
+
+impl From<Option<&str>> for PnSchemeParams {
+    fn from(value: Option<&str>) -> Self {
+        PnSchemeParams {
+            scheme: value.map(String::from),
+        }
+    }
+}
+
+impl ParamsCmd for PnSchemeParams {
+    fn cmd(self) -> Command {
+        Command::PnScheme(self)
+    }
+}
+
+/// Params for the `unit` command, which shows the unit of the item if no
+/// unit is given
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct UnitParams {
+    pub pn: String,
+    pub unit: Option<Unit>,
+}
+
+impl From<(&str, Option<Unit>)> for UnitParams {
+    fn from(value: (&str, Option<Unit>)) -> Self {
+        UnitParams {
+            pn: value.0.to_string(),
+            unit: value.1,
+        }
+    }
+}
+
+impl ParamsCmd for UnitParams {
+    fn cmd(self) -> Command {
+        Command::Unit(self)
+    }
+}
+
+/// Params for the `set-attr` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct SetAttrParams {
+    pub pn: String,
+    pub key: String,
+    pub value: String,
+    pub kind: AttributeType,
+}
+
+impl From<(&str, &str, &str, Option<AttributeType>)> for SetAttrParams {
+    fn from(value: (&str, &str, &str, Option<AttributeType>)) -> Self {
+        SetAttrParams {
+            pn: value.0.to_string(),
+            key: value.1.to_string(),
+            value: value.2.to_string(),
+            kind: value.3.unwrap_or_default(),
+        }
+    }
+}
+
+impl ParamsCmd for SetAttrParams {
+    fn cmd(self) -> Command {
+        Command::SetAttr(self)
+    }
+}
+
+/// Params for the `get-attr` command, which shows all the attributes of the
+/// item if no key is given
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct GetAttrParams {
+    pub pn: String,
+    pub key: Option<String>,
+    pub output: OutputFormat,
+}
+
+impl From<(&str, Option<&str>, Option<OutputFormat>)> for GetAttrParams {
+    fn from(value: (&str, Option<&str>, Option<OutputFormat>)) -> Self {
+        GetAttrParams {
+            pn: value.0.to_string(),
+            key: value.1.map(String::from),
+            output: value.2.unwrap_or_default(),
+        }
+    }
+}
+
+impl ParamsCmd for GetAttrParams {
+    fn cmd(self) -> Command {
+        Command::GetAttr(self)
+    }
+}
+
+/// Params for the `remove-attr` command
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct RemoveAttrParams {
+    pub pn: String,
+    pub key: String,
+}
+
+impl From<(&str, &str)> for RemoveAttrParams {
+    fn from(value: (&str, &str)) -> Self {
+        RemoveAttrParams {
+            pn: value.0.to_string(),
+            key: value.1.to_string(),
+        }
+    }
+}
+
+impl ParamsCmd for RemoveAttrParams {
+    fn cmd(self) -> Command {
+        Command::RemoveAttr(self)
+    }
+}
+
+// ====================================================================
+// parser helper functions
+// ====================================================================
+
+/// parser for a cmd followed with params.
+fn cmd<I, O, E: ParseError<I>, F, T>(cmd: T, parser: F) -> impl FnMut(I) -> IResult<I, O, E>
+where
+    I: InputTake + Compare<T>,
+    T: InputLength + Clone,
+    F: Parser<I, O, E>,
+{
+    preceded(tag(cmd), parser)
+}
+
+/// parser for a whitespace separated param
+fn param<I, O, E: ParseError<I>, F>(mut parser: F) -> impl FnMut(I) -> IResult<I, O, E>
+where
+    I: InputTakeAtPosition,
+    <I as InputTakeAtPosition>::Item: AsChar + Clone,
+    F: Parser<I, O, E>,
+{
+    move |input: I| {
+        let (input, _) = space1(input)?;
+        parser.parse(input)
+    }
+}
+
+/// A PN is alphanum and can contain `.`, `-` or `_`
+fn pn(input: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c.is_alphanumeric() || ".-_".find(c).is_some())(input)
+}
+
+#[allow(rustdoc::invalid_html_tags)]
+/// get the <name> param, allowing direct
+///
+/// A name can start and end with `"`
+fn name(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(char('"'), take_till(|c| c == '"'), char('"')),
+        take_while1(|c: char| c.is_alphanumeric() || ".-_".find(c).is_some()),
+    ))(input)
+}
+
+/// A file path, which must be surrounded with `"` if it contains spaces
+fn path(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(char('"'), take_till1(|c| c == '"'), char('"')),
+        take_till1(|c: char| c.is_whitespace()),
+    ))(input)
+}
+
+/// An attribute key is alphanum and can contain `.`, `-` or `_`, but can't
+/// start with `-` so it is not taken for an option
+fn key(input: &str) -> IResult<&str, &str> {
+    verify(pn, |key: &str| !key.starts_with('-'))(input)
+}
+
+/// `--attr <key>=<value>` option, the value being surrounded with `"` if it
+/// contains spaces
+fn attribute_filter(input: &str) -> IResult<&str, (&str, &str)> {
+    preceded(tag("--attr"), param(separated_pair(key, char('='), path)))(input)
+}
+
+/// Parser for a number
+fn quantity(input: &str) -> IResult<&str, usize> {
+    map_res(digit1, |s: &str| s.parse::<usize>())(input)
+}
+
+/// Quantity of a link, with an optional unit, e.g. `25`, `500mm` or
+/// `"2.5 m"`
+fn link_quantity(input: &str) -> IResult<&str, (f64, Option<Unit>)> {
+    map_res(path, parse_link_quantity)(input)
+}
+
+/// A unit of measure, by its symbol, e.g. `kg`
+fn unit(input: &str) -> IResult<&str, Unit> {
+    map_res(pn, Unit::parse)(input)
+}
+
+/// Column mapping of a CSV import, e.g. `pn=Part Number,name=Designation`,
+/// which must be surrounded with `"` if a column contains spaces
+fn column_mapping(input: &str) -> IResult<&str, Vec<(ImportField, &str)>> {
+    alt((
+        delimited(char('"'), mapping(|c| c == ',' || c == '"'), char('"')),
+        mapping(|c| c == ',' || c.is_whitespace()),
+    ))(input)
+}
+
+/// `<field>=<column>` pairs separated with `,`, each column ending with a
+/// character matching `end`
+fn mapping<'a>(
+    end: fn(char) -> bool,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<(ImportField, &'a str)>> {
+    let field = alt((
+        value(ImportField::Pn, tag("pn")),
+        value(ImportField::Name, tag("name")),
+        value(ImportField::ParentPn, tag("parent_pn")),
+        value(ImportField::ChildPn, tag("child_pn")),
+        value(ImportField::Quantity, tag("quantity")),
+    ));
+    separated_list1(char(','), separated_pair(field, char('='), take_till1(end)))
+}
+
+/// `--output <format>` option, the format being `text`, `json` or `csv`
+fn output(input: &str) -> IResult<&str, OutputFormat> {
+    let format = alt((
+        value(OutputFormat::Text, tag("text")),
+        value(OutputFormat::Json, tag("json")),
+        value(OutputFormat::Csv, tag("csv")),
+    ));
+    preceded(tag("--output"), param(format))(input)
+}
+
+/// End of line parser
+fn eol(input: &str) -> IResult<&str, ()> {
+    pair(multispace0, eof)(input).map(|(i, (_, _))| (i, ()))
+}
+
+// ====================================================================
+// command parsers
+// ====================================================================
+
+/// `create <name> [--category <category>]`
+fn cmd_create(input: &str) -> IResult<&str, Command> {
+    let category = preceded(tag("--category"), param(pn));
+    let params = pair(param(pn), opt(param(category)));
+    cmd("create", params)(input).cmd_n::<CreateParams>()
+}
+
+/// `add <pn> <name>`
+fn cmd_add(input: &str) -> IResult<&str, Command> {
+    let params = pair(param(pn), param(name));
+    cmd("add", params)(input).cmd_n::<AddParams>()
+}
+
+/// `list [--output <format>]`
+fn cmd_list(input: &str) -> IResult<&str, Command> {
+    let params = opt(param(output));
+    cmd("list", params)(input).cmd_n::<ListParams>()
+}
+
+/// `search <pattern> [--maturity <maturity>] [--version <n>] [--attr <key>=<value>]...
+/// [--output <format>]`
+fn cmd_search(input: &str) -> IResult<&str, Command> {
+    let maturity = alt((
+        value(ItemMaturity::InProgress, tag("in-progress")),
+        value(ItemMaturity::InReview, tag("in-review")),
+        value(ItemMaturity::Released, tag("released")),
+        value(ItemMaturity::Obsolete, tag("obsolete")),
+    ));
+    let params = tuple((
+        param(path),
+        opt(param(preceded(tag("--maturity"), param(maturity)))),
+        opt(param(preceded(tag("--version"), param(quantity)))),
+        many0(param(attribute_filter)),
+        opt(param(output)),
+    ));
+    cmd("search", params)(input).cmd_n::<SearchParams>()
+}
+
+/// `set-attr <pn> <key> <value> [--type <type>]`
+fn cmd_set_attr(input: &str) -> IResult<&str, Command> {
+    let kind = alt((
+        value(AttributeType::Text, tag("text")),
+        value(AttributeType::Number, tag("number")),
+        value(AttributeType::Bool, tag("bool")),
+        value(AttributeType::Date, tag("date")),
+    ));
+    let params = tuple((
+        param(pn),
+        param(key),
+        param(name),
+        opt(param(preceded(tag("--type"), param(kind)))),
+    ));
+    cmd("set-attr", params)(input).cmd_n::<SetAttrParams>()
+}
+
+/// `get-attr <pn> [<key>] [--output <format>]`
+fn cmd_get_attr(input: &str) -> IResult<&str, Command> {
+    let params = tuple((param(pn), opt(param(key)), opt(param(output))));
+    cmd("get-attr", params)(input).cmd_n::<GetAttrParams>()
+}
+
+/// `remove-attr <pn> <key>`
+fn cmd_remove_attr(input: &str) -> IResult<&str, Command> {
+    let params = pair(param(pn), param(key));
+    cmd("remove-attr", params)(input).cmd_n::<RemoveAttrParams>()
+}
+
+/// `review <pn>`, `release <pn>` or `obsolete <pn>`
+fn cmd_set_maturity(input: &str) -> IResult<&str, Command> {
+    let maturity = alt((
+        value(ItemMaturity::InReview, tag("review")),
+        value(ItemMaturity::Released, tag("release")),
+        value(ItemMaturity::Obsolete, tag("obsolete")),
+    ));
+    let (input, (maturity, pn)) = pair(maturity, param(pn))(input)?;
+    Ok((input, (pn, maturity))).cmd_n::<SetMaturityParams>()
+}
+
+/// `revise <pn>`
+fn cmd_revise(input: &str) -> IResult<&str, Command> {
+    let params = param(pn);
+    cmd("revise", params)(input).cmd_n::<ReviseParams>()
+}
+
+/// `revisions <pn>`
+fn cmd_revisions(input: &str) -> IResult<&str, Command> {
+    let params = param(pn);
+    cmd("revisions", params)(input).cmd_n::<RevisionsParams>()
+}
+
+/// `run <path> [--stop-on-error] [--transaction]`
+fn cmd_run(input: &str) -> IResult<&str, Command> {
+    let options = alt((tag("--stop-on-error"), tag("--transaction")));
+    let params = pair(param(path), many0(param(options)));
+    cmd("run", params)(input).cmd_n::<RunParams>()
+}
+
+/// `import-csv [--items <path>] [--links <path>] [--columns <mapping>] [--dry-run]`
+///
+/// At least one of the items or links files is required.
+fn cmd_import_csv(input: &str) -> IResult<&str, Command> {
+    let option = alt((
+        map(preceded(tag("--items"), param(path)), ImportOption::Items),
+        map(preceded(tag("--links"), param(path)), ImportOption::Links),
+        map(
+            preceded(tag("--columns"), param(column_mapping)),
+            ImportOption::Columns,
+        ),
+        value(ImportOption::DryRun, tag("--dry-run")),
+    ));
+    let params = verify(many1(param(option)), |options: &[ImportOption]| {
+        options
+            .iter()
+            .any(|o| matches!(o, ImportOption::Items(_) | ImportOption::Links(_)))
+    });
+    cmd("import-csv", params)(input).cmd_n::<ImportCsvParams>()
+}
+
+/// `export-bom <pn> <path>`
+fn cmd_export_bom(input: &str) -> IResult<&str, Command> {
+    let params = pair(param(pn), param(path));
+    cmd("export-bom", params)(input).cmd_n::<ExportBomParams>()
+}
+
+/// `export-graph <pn> <path>`
+fn cmd_export_graph(input: &str) -> IResult<&str, Command> {
+    let params = pair(param(pn), param(path));
+    cmd("export-graph", params)(input).cmd_n::<ExportGraphParams>()
+}
+
+/// `pn-scheme [<scheme>]`
+fn cmd_pn_scheme(input: &str) -> IResult<&str, Command> {
+    cmd("pn-scheme", opt(param(pn)))(input).cmd_n::<PnSchemeParams>()
+}
+
+/// `unit <pn> [<unit>]`
+fn cmd_unit(input: &str) -> IResult<&str, Command> {
+    let params = pair(param(pn), opt(param(unit)));
+    cmd("unit", params)(input).cmd_n::<UnitParams>()
+}
+
+/// `check`
+fn cmd_check(input: &str) -> IResult<&str, Command> {
+    tag("check")(input).cmd_0(Command::Check)
+}
+
+/// `exit`
+fn cmd_exit(input: &str) -> IResult<&str, Command> {
+    tag("exit")(input).cmd_0(Command::Exit)
+}
+
+/// `help`
+fn cmd_help(input: &str) -> IResult<&str, Command> {
+    tag("help")(input).cmd_0(Command::Help)
+}
+
+/// `tree <pn> [--depth <n>] [--output <format>]`
+fn cmd_tree(input: &str) -> IResult<&str, Command> {
+    let depth = preceded(tag("--depth"), param(quantity));
+    let params = tuple((param(pn), opt(param(depth)), opt(param(output))));
+    cmd("tree", params)(input).cmd_n::<TreeParams>()
+}
+
+/// `where-used <pn> [--all] [--output <format>]`
+fn cmd_where_used(input: &str) -> IResult<&str, Command> {
+    let params = tuple((param(pn), opt(param(tag("--all"))), opt(param(output))));
+    cmd("where-used", params)(input).cmd_n::<WhereUsedParams>()
+}
+
+/// `add-child <parent-pn> <child-pn> <quantity>[<unit>]`
+fn cmd_add_child(input: &str) -> IResult<&str, Command> {
+    let params = tuple((param(pn), param(pn), param(link_quantity)));
+    cmd("add-child", params)(input).cmd_n::<AddChildParams>()
+}
+
+/// `remove-child <parent-pn> <child-pn>`
+fn cmd_remove_child(input: &str) -> IResult<&str, Command> {
+    let params = pair(param(pn), param(pn));
+    cmd("remove-child", params)(input).cmd_n::<RemoveChildParams>()
+}
+
+/// `set-qty <parent-pn> <child-pn> <quantity>[<unit>]`
+fn cmd_set_quantity(input: &str) -> IResult<&str, Command> {
+    let params = tuple((param(pn), param(pn), param(link_quantity)));
+    cmd("set-qty", params)(input).cmd_n::<SetQuantityParams>()
+}
+
+/// `delete <pn> [--force]`
+fn cmd_delete(input: &str) -> IResult<&str, Command> {
+    let params = pair(param(pn), opt(param(tag("--force"))));
+    cmd("delete", params)(input).cmd_n::<DeleteParams>()
+}
+
+/// `stock <pn> [--leaves] [--output <format>]`
+fn cmd_stock(input: &str) -> IResult<&str, Command> {
+    let params = tuple((param(pn), opt(param(tag("--leaves"))), opt(param(output))));
+    preceded(tag("stock"), params)(input).cmd_n::<StockParams>()
+}
+
+/// Get the command of the input
+pub fn get_command(input: &str) -> Result<Command, nom::Err<nom::error::Error<&str>>> {
+    delimited(
+        space0,
+        // alt() takes at most 21 parsers
+        alt((
+            alt((
+                cmd_create,
+                cmd_add,
+                cmd_list,
+                cmd_search,
+                cmd_add_child,
+                cmd_remove_child,
+                cmd_set_quantity,
+                cmd_delete,
+                cmd_tree,
+                cmd_help,
+                cmd_exit,
+                cmd_where_used,
+                cmd_stock,
+                cmd_set_maturity,
+                cmd_revise,
+                cmd_revisions,
+                cmd_check,
+                cmd_run,
+                cmd_import_csv,
+                cmd_export_bom,
+                cmd_export_graph,
+            )),
+            cmd_pn_scheme,
+            cmd_set_attr,
+            cmd_get_attr,
+            cmd_remove_attr,
+            cmd_unit,
+        )),
+        eol,
+    )(input)
+    .map(|(_, cmd)| cmd)
+}
+
+/// =================================================================
+/// Test
+/// =================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pn() {
+        assert_eq!(pn("PN"), Ok(("", "PN")));
+        assert_eq!(pn("PN111"), Ok(("", "PN111")));
+        assert_eq!(pn("PN111AA"), Ok(("", "PN111AA")));
+        assert_eq!(pn("PN111-AA"), Ok(("", "PN111-AA")));
+        assert_eq!(pn("PN111_AA"), Ok(("", "PN111_AA")));
+        assert_eq!(pn("PN111.AA"), Ok(("", "PN111.AA")));
+        assert_eq!(pn("PN111$AA"), Ok(("$AA", "PN111")));
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(name("NAME"), Ok(("", "NAME")));
+        assert_eq!(name("\"NAME\""), Ok(("", "NAME")));
+        assert_eq!(
+            name("\"NAME WITH - _ CHARS\""),
+            Ok(("", "NAME WITH - _ CHARS"))
+        );
+    }
+
+    #[test]
+    fn test_path() {
+        assert_eq!(path("file.txt"), Ok(("", "file.txt")));
+        assert_eq!(
+            path("../dir/file.txt next"),
+            Ok((" next", "../dir/file.txt"))
+        );
+        assert_eq!(path("\"my file.txt\""), Ok(("", "my file.txt")));
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(
+            Command::Run(RunParams {
+                path: "example/dataset.txt".to_string(),
+                stop_on_error: false,
+                transaction: false
+            }),
+            get_command("run example/dataset.txt").unwrap()
+        );
+        assert_eq!(
+            Command::Run(RunParams {
+                path: "my script.txt".to_string(),
+                stop_on_error: true,
+                transaction: false
+            }),
+            get_command("run \"my script.txt\" --stop-on-error").unwrap()
+        );
+        assert_eq!(
+            Command::Run(RunParams {
+                path: "script.txt".to_string(),
+                stop_on_error: false,
+                transaction: true
+            }),
+            get_command("run script.txt --transaction").unwrap()
+        );
+        assert!(get_command("run").is_err());
+    }
+
+    #[test]
+    fn test_import_csv() {
+        assert_eq!(
+            Command::ImportCsv(ImportCsvParams {
+                items: Some("items.csv".to_string()),
+                links: None,
+                columns: ImportColumns::default(),
+                dry_run: false,
+            }),
+            get_command("import-csv --items items.csv").unwrap()
+        );
+        let columns = ImportColumns {
+            pn: "Part Number".to_string(),
+            quantity: "Qty".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Command::ImportCsv(ImportCsvParams {
+                items: Some("my items.csv".to_string()),
+                links: Some("links.csv".to_string()),
+                columns,
+                dry_run: true,
+            }),
+            get_command(
+                "import-csv --dry-run --items \"my items.csv\" --links links.csv \
+                --columns \"pn=Part Number,quantity=Qty\""
+            )
+            .unwrap()
+        );
+        let cmd = get_command("import-csv --links links.csv --columns child_pn=Child").unwrap();
+        let Command::ImportCsv(params) = cmd else {
+            panic!("unexpected command {cmd:?}");
+        };
+        assert_eq!("Child", params.columns.child_pn);
+        assert!(get_command("import-csv").is_err());
+        assert!(get_command("import-csv --dry-run").is_err());
+        assert!(get_command("import-csv --items a.csv --columns size=Size").is_err());
+    }
+
+    #[test]
+    fn test_export_bom() {
+        assert_eq!(
+            Command::ExportBom(ExportBomParams {
+                pn: "PN".to_string(),
+                path: "my bom.xlsx".to_string(),
+            }),
+            get_command("export-bom PN \"my bom.xlsx\"").unwrap()
+        );
+        assert!(get_command("export-bom PN").is_err());
+        assert_eq!(
+            Command::ExportGraph(ExportGraphParams {
+                pn: "PN".to_string(),
+                path: "graph.mmd".to_string(),
+            }),
+            get_command("export-graph PN graph.mmd").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        assert_eq!(
+            Command::Search(SearchParams {
+                query: SearchQuery::new("chair"),
+                output: OutputFormat::Text,
+            }),
+            get_command("search chair").unwrap()
+        );
+        assert_eq!(
+            Command::Search(SearchParams {
+                query: SearchQuery {
+                    pattern: "big chair*".to_string(),
+                    maturity: Some(ItemMaturity::Released),
+                    version: Some(2),
+                    attributes: Vec::new(),
+                },
+                output: OutputFormat::Json,
+            }),
+            get_command("search \"big chair*\" --maturity released --version 2 --output json")
+                .unwrap()
+        );
+        let cmd = get_command("search \"*\" --attr material=steel --attr color=\"dark blue\"");
+        let Command::Search(params) = cmd.unwrap() else {
+            panic!("unexpected command");
+        };
+        assert_eq!(
+            vec![
+                ("material".to_string(), "steel".to_string()),
+                ("color".to_string(), "dark blue".to_string())
+            ],
+            params.query.attributes
+        );
+        assert!(get_command("search").is_err());
+        assert!(get_command("search chair --maturity done").is_err());
+    }
+
+    #[test]
+    fn test_list_ok() {
+        let list = |output| Command::List(ListParams { output });
+        assert_eq!(list(OutputFormat::Text), get_command("list").unwrap());
+        assert_eq!(list(OutputFormat::Text), get_command("  list").unwrap());
+        assert_eq!(
+            list(OutputFormat::Text),
+            get_command("  \tlist \t ").unwrap()
+        );
+        assert_eq!(
+            list(OutputFormat::Json),
+            get_command("list --output json").unwrap()
+        );
+        assert_eq!(
+            list(OutputFormat::Csv),
+            get_command("list --output \t csv").unwrap()
+        );
+        assert!(get_command("list --output").is_err());
+        assert!(get_command("list --output xml").is_err());
+        assert!(get_command("listing").is_err());
+    }
+
+    #[test]
+    fn test_check_ok() {
+        assert_eq!(Command::Check, get_command("check").unwrap());
+        assert!(get_command("check PN").is_err());
+    }
+
+    #[test]
+    fn test_exit_ok() {
+        assert_eq!(Command::Exit, get_command("exit").unwrap());
+        assert_eq!(Command::Exit, get_command("  exit").unwrap());
+        assert_eq!(Command::Exit, get_command("  exit  ").unwrap());
+    }
+
+    #[test]
+    fn test_exit_err() {
+        assert!(get_command("exi").is_err());
+        assert!(get_command("exit4").is_err());
+        assert!(get_command("exit 4").is_err());
     }
 
     #[test]
@@ -380,9 +1285,67 @@ mod tests {
         assert_eq!(
             Command::Tree(TreeParams {
                 pn: "PN".to_string(),
+                depth: None,
+                output: OutputFormat::Text,
+            }),
+            cmd
+        );
+        let cmd = get_command("tree PN --depth \t 2").unwrap();
+        assert_eq!(
+            Command::Tree(TreeParams {
+                pn: "PN".to_string(),
+                depth: Some(2),
+                output: OutputFormat::Text,
+            }),
+            cmd
+        );
+        let cmd = get_command("tree PN --depth 2 --output json").unwrap();
+        assert_eq!(
+            Command::Tree(TreeParams {
+                pn: "PN".to_string(),
+                depth: Some(2),
+                output: OutputFormat::Json,
+            }),
+            cmd
+        );
+        let cmd = get_command("tree PN --output csv").unwrap();
+        assert_eq!(
+            Command::Tree(TreeParams {
+                pn: "PN".to_string(),
+                depth: None,
+                output: OutputFormat::Csv,
             }),
             cmd
         );
+        assert!(get_command("tree PN --depth").is_err());
+    }
+
+    #[test]
+    fn test_where_used() {
+        assert_eq!(
+            Command::WhereUsed(WhereUsedParams {
+                pn: "PN".to_string(),
+                all: false,
+                output: OutputFormat::Text,
+            }),
+            get_command("where-used PN").unwrap()
+        );
+        assert_eq!(
+            Command::WhereUsed(WhereUsedParams {
+                pn: "PN".to_string(),
+                all: true,
+                output: OutputFormat::Text,
+            }),
+            get_command("where-used PN --all").unwrap()
+        );
+        assert_eq!(
+            Command::WhereUsed(WhereUsedParams {
+                pn: "PN".to_string(),
+                all: true,
+                output: OutputFormat::Csv,
+            }),
+            get_command("where-used PN --all --output csv").unwrap()
+        );
     }
 
     #[test]
@@ -392,10 +1355,146 @@ mod tests {
             Command::AddChild(AddChildParams {
                 parent_pn: "PN1".to_string(),
                 child_pn: "PN2".to_string(),
-                quantity: 456
+                quantity: 456.0,
+                unit: None
             }),
             cmd
         );
+        assert_eq!(
+            Command::AddChild(AddChildParams {
+                parent_pn: "PN1".to_string(),
+                child_pn: "PN2".to_string(),
+                quantity: 2.5,
+                unit: Some(Unit::Metre)
+            }),
+            get_command("add-child PN1 PN2 \"2.5 m\"").unwrap()
+        );
+        assert!(get_command("add-child PN1 PN2 0").is_err());
+        assert!(get_command("add-child PN1 PN2 3ft").is_err());
+    }
+
+    #[test]
+    fn test_stock() {
+        assert_eq!(
+            Command::Stock(StockParams {
+                pn: "PN".to_string(),
+                leaves_only: false,
+                output: OutputFormat::Text,
+            }),
+            get_command("stock PN").unwrap()
+        );
+        assert_eq!(
+            Command::Stock(StockParams {
+                pn: "PN".to_string(),
+                leaves_only: true,
+                output: OutputFormat::Text,
+            }),
+            get_command(" stock \t PN  --leaves ").unwrap()
+        );
+        assert_eq!(
+            Command::Stock(StockParams {
+                pn: "PN".to_string(),
+                leaves_only: false,
+                output: OutputFormat::Json,
+            }),
+            get_command("stock PN --output json").unwrap()
+        );
+        assert!(get_command("stock PN --leave").is_err());
+    }
+
+    #[test]
+    fn test_set_maturity() {
+        assert_eq!(
+            Command::SetMaturity(SetMaturityParams {
+                pn: "PN".to_string(),
+                maturity: ItemMaturity::Released
+            }),
+            get_command("release PN").unwrap()
+        );
+        assert_eq!(
+            Command::SetMaturity(SetMaturityParams {
+                pn: "PN".to_string(),
+                maturity: ItemMaturity::InReview
+            }),
+            get_command(" review  PN ").unwrap()
+        );
+        assert_eq!(
+            Command::SetMaturity(SetMaturityParams {
+                pn: "PN".to_string(),
+                maturity: ItemMaturity::Obsolete
+            }),
+            get_command("obsolete PN").unwrap()
+        );
+        assert!(get_command("release").is_err());
+    }
+
+    #[test]
+    fn test_revisions() {
+        assert_eq!(
+            Command::Revise(ReviseParams {
+                pn: "PN".to_string()
+            }),
+            get_command("revise PN").unwrap()
+        );
+        assert_eq!(
+            Command::Revisions(RevisionsParams {
+                pn: "PN".to_string()
+            }),
+            get_command("revisions PN").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_remove_child() {
+        assert_eq!(
+            Command::RemoveChild(RemoveChildParams {
+                parent_pn: "PN1".to_string(),
+                child_pn: "PN2".to_string(),
+            }),
+            get_command("remove-child PN1 PN2").unwrap()
+        );
+        assert!(get_command("remove-child PN1").is_err());
+    }
+
+    #[test]
+    fn test_set_quantity() {
+        assert_eq!(
+            Command::SetQuantity(SetQuantityParams {
+                parent_pn: "PN1".to_string(),
+                child_pn: "PN2".to_string(),
+                quantity: 12.0,
+                unit: None
+            }),
+            get_command("set-qty PN1 PN2 12").unwrap()
+        );
+        assert_eq!(
+            Command::SetQuantity(SetQuantityParams {
+                parent_pn: "PN1".to_string(),
+                child_pn: "PN2".to_string(),
+                quantity: 500.0,
+                unit: Some(Unit::Millimetre)
+            }),
+            get_command("set-qty PN1 PN2 500mm").unwrap()
+        );
+        assert!(get_command("set-qty PN1 PN2").is_err());
+    }
+
+    #[test]
+    fn test_delete() {
+        assert_eq!(
+            Command::Delete(DeleteParams {
+                pn: "PN".to_string(),
+                force: false
+            }),
+            get_command("delete PN").unwrap()
+        );
+        assert_eq!(
+            Command::Delete(DeleteParams {
+                pn: "PN".to_string(),
+                force: true
+            }),
+            get_command("delete PN --force").unwrap()
+        );
     }
 
     #[test]
@@ -404,8 +1503,97 @@ mod tests {
         assert_eq!(
             Command::Create(CreateParams {
                 name: "NAME".to_string(),
+                category: None,
             }),
             cmd
         );
+        assert_eq!(
+            Command::Create(CreateParams {
+                name: "NAME".to_string(),
+                category: Some("ASM".to_string()),
+            }),
+            get_command("create NAME --category ASM").unwrap()
+        );
+        assert!(get_command("create NAME --category").is_err());
+    }
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            Command::SetAttr(SetAttrParams {
+                pn: "PN".to_string(),
+                key: "material".to_string(),
+                value: "stainless steel".to_string(),
+                kind: AttributeType::Text,
+            }),
+            get_command("set-attr PN material \"stainless steel\"").unwrap()
+        );
+        assert_eq!(
+            Command::SetAttr(SetAttrParams {
+                pn: "PN".to_string(),
+                key: "weight".to_string(),
+                value: "-1.5".to_string(),
+                kind: AttributeType::Number,
+            }),
+            get_command("set-attr PN weight -1.5 --type number").unwrap()
+        );
+        assert!(get_command("set-attr PN weight").is_err());
+        assert!(get_command("set-attr PN weight 2 --type float").is_err());
+        assert_eq!(
+            Command::GetAttr(GetAttrParams {
+                pn: "PN".to_string(),
+                key: None,
+                output: OutputFormat::Json,
+            }),
+            get_command("get-attr PN --output json").unwrap()
+        );
+        assert_eq!(
+            Command::GetAttr(GetAttrParams {
+                pn: "PN".to_string(),
+                key: Some("weight".to_string()),
+                output: OutputFormat::Text,
+            }),
+            get_command("get-attr PN weight").unwrap()
+        );
+        assert_eq!(
+            Command::RemoveAttr(RemoveAttrParams {
+                pn: "PN".to_string(),
+                key: "weight".to_string(),
+            }),
+            get_command("remove-attr PN weight").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pn_scheme() {
+        assert_eq!(
+            Command::PnScheme(PnSchemeParams { scheme: None }),
+            get_command("pn-scheme").unwrap()
+        );
+        assert_eq!(
+            Command::PnScheme(PnSchemeParams {
+                scheme: Some("luhn".to_string())
+            }),
+            get_command("pn-scheme luhn").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unit() {
+        assert_eq!(
+            Command::Unit(UnitParams {
+                pn: "PN".to_string(),
+                unit: None
+            }),
+            get_command("unit PN").unwrap()
+        );
+        assert_eq!(
+            Command::Unit(UnitParams {
+                pn: "PN".to_string(),
+                unit: Some(Unit::Kilogram)
+            }),
+            get_command("unit PN kg").unwrap()
+        );
+        assert!(get_command("unit PN parsec").is_err());
     }
 }
@@ -0,0 +1,202 @@
+//! Custom attributes of the items, e.g. their material, supplier reference
+//! or weight
+
+use std::collections::BTreeMap;
+
+use rusqlite::{
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+    ToSql,
+};
+use serde::{Serialize, Serializer};
+
+use crate::{Error, Result};
+
+/// Attributes of an item, by key
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+/// Type of the value of an attribute
+///
+/// The names are stored in the database and must not change
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeType {
+    #[default]
+    Text,
+    Number,
+    Bool,
+    /// A date in the `YYYY-MM-DD` format
+    Date,
+}
+
+impl AttributeType {
+    fn name(self) -> &'static str {
+        match self {
+            AttributeType::Text => "text",
+            AttributeType::Number => "number",
+            AttributeType::Bool => "bool",
+            AttributeType::Date => "date",
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromSql for AttributeType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "text" => Ok(AttributeType::Text),
+            "number" => Ok(AttributeType::Number),
+            "bool" => Ok(AttributeType::Bool),
+            "date" => Ok(AttributeType::Date),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl ToSql for AttributeType {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.name()))
+    }
+}
+
+/// A typed value of an attribute
+///
+/// It is serialized as a JSON string, number or boolean, a date being a
+/// string.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeValue {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    /// A date in the `YYYY-MM-DD` format
+    Date(String),
+}
+
+impl AttributeValue {
+    /// Parse the text of a value of the given type
+    ///
+    /// Numbers must be finite, booleans are `true` or `false` and dates
+    /// are valid `YYYY-MM-DD` dates.
+    pub fn parse(kind: AttributeType, value: &str) -> Result<Self> {
+        let invalid = || Error::InvalidAttributeValue {
+            kind,
+            value: value.to_string(),
+        };
+        match kind {
+            AttributeType::Text => Ok(AttributeValue::Text(value.to_string())),
+            AttributeType::Number => value
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .map(AttributeValue::Number)
+                .ok_or_else(invalid),
+            AttributeType::Bool => match value {
+                "true" => Ok(AttributeValue::Bool(true)),
+                "false" => Ok(AttributeValue::Bool(false)),
+                _ => Err(invalid()),
+            },
+            AttributeType::Date if is_date(value) => Ok(AttributeValue::Date(value.to_string())),
+            AttributeType::Date => Err(invalid()),
+        }
+    }
+
+    /// Type of the value
+    pub fn kind(&self) -> AttributeType {
+        match self {
+            AttributeValue::Text(_) => AttributeType::Text,
+            AttributeValue::Number(_) => AttributeType::Number,
+            AttributeValue::Bool(_) => AttributeType::Bool,
+            AttributeValue::Date(_) => AttributeType::Date,
+        }
+    }
+}
+
+/// The text of a value, as given to [AttributeValue::parse]
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::Text(text) | AttributeValue::Date(text) => write!(f, "{text}"),
+            AttributeValue::Number(number) => write!(f, "{number}"),
+            AttributeValue::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl Serialize for AttributeValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            AttributeValue::Text(text) | AttributeValue::Date(text) => {
+                serializer.serialize_str(text)
+            }
+            AttributeValue::Number(number) => serializer.serialize_f64(*number),
+            AttributeValue::Bool(value) => serializer.serialize_bool(*value),
+        }
+    }
+}
+
+/// Check that `value` is a valid `YYYY-MM-DD` date
+fn is_date(value: &str) -> bool {
+    let parts = value.split('-').collect::<Vec<_>>();
+    let [year, month, day] = parts[..] else {
+        return false;
+    };
+    let number = |part: &str, len: usize| {
+        (part.len() == len && part.chars().all(|c| c.is_ascii_digit()))
+            .then(|| part.parse::<u32>().ok())
+            .flatten()
+    };
+    let (Some(year), Some(month), Some(day)) = (number(year, 4), number(month, 2), number(day, 2))
+    else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        use AttributeType::*;
+        assert_eq!(
+            AttributeValue::Text("steel".to_string()),
+            AttributeValue::parse(Text, "steel").unwrap()
+        );
+        assert_eq!(
+            AttributeValue::Number(7.5),
+            AttributeValue::parse(Number, "7.5").unwrap()
+        );
+        assert_eq!(
+            "2",
+            AttributeValue::parse(Number, "2.0").unwrap().to_string()
+        );
+        assert!(AttributeValue::parse(Number, "heavy").is_err());
+        assert!(AttributeValue::parse(Number, "inf").is_err());
+        assert_eq!(
+            AttributeValue::Bool(true),
+            AttributeValue::parse(Bool, "true").unwrap()
+        );
+        assert!(AttributeValue::parse(Bool, "yes").is_err());
+        assert_eq!(
+            AttributeValue::Date("2024-02-29".to_string()),
+            AttributeValue::parse(Date, "2024-02-29").unwrap()
+        );
+        let err = AttributeValue::parse(Date, "2023-02-29").unwrap_err();
+        assert_eq!("invalid date \"2023-02-29\"", err.to_string());
+        assert!(AttributeValue::parse(Date, "2024-1-31").is_err());
+        assert!(AttributeValue::parse(Date, "31/01/2024").is_err());
+    }
+}
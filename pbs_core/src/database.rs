@@ -1,24 +1,62 @@
 use std::hash::{Hash, Hasher};
 
-use crate::{Error, Result};
+use crate::{
+    migrations, search, AttributeType, AttributeValue, Attributes, Error, Quantity, Result,
+    SearchQuery, Unit,
+};
 use rusqlite::{
-    types::{FromSql, FromSqlResult, ToSqlOutput, Value, ValueRef},
-    Connection, ToSql,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Value, ValueRef},
+    Connection, OpenFlags, ToSql,
 };
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 
 pub struct Database(Connection);
 
-#[derive(Copy, Clone)]
+/// Lifecycle state of an item
+///
+/// The integer values are stored in the database and must not change
+///
+/// It is serialized in kebab case, e.g. `in-progress`, as it is written in
+/// the CLI commands
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum ItemMaturity {
     InProgress = 0,
     Released = 1,
+    InReview = 2,
+    Obsolete = 3,
+}
+
+impl ItemMaturity {
+    /// Check if an item can go from this maturity to the `next` one
+    ///
+    /// The lifecycle is `InProgress <-> InReview`, then `Released` (from
+    /// `InProgress` or `InReview`) and finally `Obsolete`.
+    pub fn can_become(self, next: ItemMaturity) -> bool {
+        use ItemMaturity::*;
+        matches!(
+            (self, next),
+            (InProgress, InReview)
+                | (InProgress, Released)
+                | (InReview, InProgress)
+                | (InReview, Released)
+                | (Released, Obsolete)
+        )
+    }
+
+    /// Check if an item with this maturity can't be modified anymore
+    pub fn is_frozen(self) -> bool {
+        matches!(self, ItemMaturity::Released | ItemMaturity::Obsolete)
+    }
 }
 
 impl std::fmt::Display for ItemMaturity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let maturity = match self {
             ItemMaturity::InProgress => "In progress...",
+            ItemMaturity::InReview => "In review",
             ItemMaturity::Released => "Released",
+            ItemMaturity::Obsolete => "Obsolete",
         };
         write!(f, "{maturity}")
     }
@@ -29,7 +67,9 @@ impl FromSql for ItemMaturity {
         match value.as_i64()? {
             x if x == ItemMaturity::InProgress as i64 => Ok(ItemMaturity::InProgress),
             x if x == ItemMaturity::Released as i64 => Ok(ItemMaturity::Released),
-            _ => todo!("DB : Manage the maturity conversion"),
+            x if x == ItemMaturity::InReview as i64 => Ok(ItemMaturity::InReview),
+            x if x == ItemMaturity::Obsolete as i64 => Ok(ItemMaturity::Obsolete),
+            x => Err(FromSqlError::OutOfRange(x)),
         }
     }
 }
@@ -40,11 +80,13 @@ impl ToSql for ItemMaturity {
     }
 }
 
+#[derive(Clone, Debug)]
 struct InnerItem {
     pn: String,
     name: String,
     maturity: ItemMaturity,
     version: usize,
+    unit: Unit,
 }
 
 impl InnerItem {
@@ -54,6 +96,7 @@ impl InnerItem {
             name: name.to_string(),
             version: 1,
             maturity: ItemMaturity::InProgress,
+            unit: Unit::Each,
         }
     }
 }
@@ -66,10 +109,12 @@ impl<'stmt> TryFrom<&rusqlite::Row<'stmt>> for InnerItem {
             name: value.get("name")?,
             version: value.get("version")?,
             maturity: value.get("maturity")?,
+            unit: value.get("unit")?,
         })
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct Item {
     _id: usize,
     inner: InnerItem,
@@ -95,6 +140,15 @@ impl Item {
     pub fn maturity(&self) -> ItemMaturity {
         self.inner.maturity
     }
+
+    /// Unit of measure in which the quantities of the item are counted
+    pub fn unit(&self) -> Unit {
+        self.inner.unit
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.inner.name = name.to_string();
+    }
 }
 
 impl std::fmt::Display for Item {
@@ -110,6 +164,20 @@ impl std::fmt::Display for Item {
     }
 }
 
+/// An item is serialized as a flat structure with its `pn`, `version`,
+/// `name`, `maturity` and `unit`, so it can be written as a CSV record
+impl Serialize for Item {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut item = serializer.serialize_struct("Item", 5)?;
+        item.serialize_field("pn", self.pn())?;
+        item.serialize_field("version", &self.version())?;
+        item.serialize_field("name", self.name())?;
+        item.serialize_field("maturity", &self.maturity())?;
+        item.serialize_field("unit", &self.unit())?;
+        item.end()
+    }
+}
+
 impl PartialEq for Item {
     fn eq(&self, other: &Self) -> bool {
         self._id == other._id
@@ -136,24 +204,91 @@ impl<'stmt> TryFrom<&rusqlite::Row<'stmt>> for Item {
 
 trait ErrConvert<T> {
     fn convert(self) -> Result<T>;
+
+    /// Convert a query returning no row to the given error
+    fn convert_no_rows(self, err: impl FnOnce() -> Error) -> Result<T>;
+
+    /// Convert a violation of a `UNIQUE` constraint to the given error
+    fn convert_unique(self, err: impl FnOnce() -> Error) -> Result<T>;
 }
 
 impl<T> ErrConvert<T> for rusqlite::Result<T> {
     fn convert(self) -> Result<T> {
         self.map_err(Error::DatabaseErr)
     }
+
+    fn convert_no_rows(self, err: impl FnOnce() -> Error) -> Result<T> {
+        self.map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => err(),
+            e => Error::DatabaseErr(e),
+        })
+    }
+
+    fn convert_unique(self, err: impl FnOnce() -> Error) -> Result<T> {
+        self.map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(e, _)
+                if e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+            {
+                err()
+            }
+            e => Error::DatabaseErr(e),
+        })
+    }
+}
+
+/// Check that a statement changed exactly one row, or return the given error
+fn expect_one_row(changed: usize, err: impl FnOnce() -> Error) -> Result<()> {
+    if changed != 1 {
+        return Err(err());
+    }
+    Ok(())
 }
 
 impl Database {
-    /// Open the store
+    /// Open the store, creating it if it doesn't exist
+    ///
+    /// The database schema is upgraded if it was created by an older version
     pub(crate) fn open(url: &str) -> Result<Self> {
-        let conn = Connection::open(url).convert()?;
-        for req in include_str!("db.sql").split(';').filter(|s| !s.is_empty()) {
-            conn.execute(req, ()).convert()?;
-        }
+        Database::open_with_flags(url, OpenFlags::default())
+    }
+
+    /// Open an existing store, failing if it doesn't exist
+    pub(crate) fn open_existing(url: &str) -> Result<Self> {
+        let flags = OpenFlags::default().difference(OpenFlags::SQLITE_OPEN_CREATE);
+        Database::open_with_flags(url, flags)
+    }
+
+    fn open_with_flags(url: &str, flags: OpenFlags) -> Result<Self> {
+        let mut conn = Connection::open_with_flags(url, flags).convert()?;
+        migrations::migrate(&mut conn)?;
         Ok(Database(conn))
     }
 
+    /// Write a copy of the database to a new file
+    pub(crate) fn save_as(&self, path: &str) -> Result<()> {
+        self.0
+            .execute("VACUUM INTO ?1", [path])
+            .map(|_| ())
+            .convert()
+    }
+
+    /// Start a transaction, which may be nested in another one
+    pub(crate) fn begin(&self) -> Result<()> {
+        self.0.execute_batch("SAVEPOINT pbs_transaction").convert()
+    }
+
+    /// Save all the changes made since the last call to [Database::begin]
+    pub(crate) fn commit(&self) -> Result<()> {
+        self.0.execute_batch("RELEASE pbs_transaction").convert()
+    }
+
+    /// Cancel all the changes made since the last call to [Database::begin]
+    pub(crate) fn rollback(&self) -> Result<()> {
+        self.0
+            .execute_batch("ROLLBACK TO pbs_transaction; RELEASE pbs_transaction")
+            .convert()
+    }
+
     // Get a config value from database
     pub fn get_config(&self, key: &str) -> Result<String> {
         let mut stmt = self
@@ -177,6 +312,15 @@ impl Database {
         stmt.execute((key, value)).map(|_| ()).convert()
     }
 
+    /// The current local date, as `YYYYMMDD`
+    pub(crate) fn today(&self) -> Result<String> {
+        self.0
+            .query_row("SELECT strftime('%Y%m%d', 'now', 'localtime')", [], |row| {
+                row.get(0)
+            })
+            .convert()
+    }
+
     // Add a new item to the store
     pub(crate) fn insert_item(&self, pn: &str, name: &str) -> Result<Item> {
         let inner_item = InnerItem::new(pn, name);
@@ -190,14 +334,20 @@ impl Database {
                     inner_item.maturity,
                 ),
             )
-            .convert()?;
+            .convert_unique(|| Error::DuplicatePn(pn.to_string()))?;
         let id = self.0.last_insert_rowid();
         Ok(Item::new(id as usize, inner_item))
     }
 
-    /// Retrive all [Item]s
+    /// Retrive the last revision of all [Item]s
     pub(crate) fn get_items(&self) -> Result<Vec<Item>> {
-        let mut stmt = self.0.prepare("SELECT * FROM items").convert()?;
+        let mut stmt = self
+            .0
+            .prepare(
+                "SELECT * FROM items WHERE version =
+                (SELECT MAX(version) FROM items AS revisions WHERE revisions.pn = items.pn)",
+            )
+            .convert()?;
         let items = stmt
             .query_map([], |row| Item::try_from(row))
             .convert()?
@@ -206,52 +356,303 @@ impl Database {
         Ok(items)
     }
 
+    /// Retrieve the [Item]s matching the `query`, ordered by PN and version
+    pub(crate) fn search_items(&self, query: &SearchQuery) -> Result<Vec<Item>> {
+        let matches = if query.is_glob() {
+            "(lower(pn) GLOB ?1 OR lower(name) GLOB ?1)"
+        } else {
+            "(pn LIKE ?1 ESCAPE '\\' OR name LIKE ?1 ESCAPE '\\')"
+        };
+        let pattern = query.sql_pattern();
+        let mut params: Vec<&dyn ToSql> = vec![&pattern, &query.maturity, &query.version];
+        // each attribute is matched with its key, its text and its number
+        let filters = query
+            .attributes
+            .iter()
+            .map(|(key, value)| (key, value.to_lowercase(), value.parse::<f64>().ok()))
+            .collect::<Vec<_>>();
+        let mut attributes = String::new();
+        for (key, value, number) in &filters {
+            let index = params.len();
+            let op = if search::is_glob(value) { "GLOB" } else { "=" };
+            attributes.push_str(&format!(
+                "AND EXISTS (SELECT 1 FROM attributes WHERE id_item = items.id
+                    AND key = ?{key}
+                    AND CASE WHEN type = 'number'
+                        THEN CAST(value AS REAL) = ?{number}
+                        ELSE lower(value) {op} ?{value}
+                    END)\n",
+                key = index + 1,
+                value = index + 2,
+                number = index + 3,
+            ));
+            params.extend([key as &dyn ToSql, value, number]);
+        }
+        let mut stmt = self
+            .0
+            .prepare(&format!(
+                "SELECT * FROM items WHERE {matches}
+                AND (?2 IS NULL OR maturity = ?2)
+                AND CASE WHEN ?3 IS NULL
+                    THEN version = (SELECT MAX(version) FROM items AS revisions
+                        WHERE revisions.pn = items.pn)
+                    ELSE version = ?3
+                END
+                {attributes}
+                ORDER BY pn, version"
+            ))
+            .convert()?;
+        let items = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                Item::try_from(row)
+            })
+            .convert()?
+            .filter_map(|i| i.ok())
+            .collect::<Vec<_>>();
+        Ok(items)
+    }
+
+    /// Retrive all the revisions of an item, ordered by version
+    pub(crate) fn get_revisions(&self, pn: &str) -> Result<Vec<Item>> {
+        let mut stmt = self
+            .0
+            .prepare("SELECT * FROM items WHERE pn = ?1 ORDER BY version")
+            .convert()?;
+        let items = stmt
+            .query_map([pn], |row| Item::try_from(row))
+            .convert()?
+            .filter_map(|i| i.ok())
+            .collect::<Vec<_>>();
+        Ok(items)
+    }
+
+    /// Create a new in progress revision of an item, with the same name,
+    /// children and attributes
+    pub(crate) fn insert_revision(&mut self, item: &Item) -> Result<Item> {
+        let mut inner_item = InnerItem::new(item.pn(), item.name());
+        inner_item.version = item.version() + 1;
+        inner_item.unit = item.unit();
+        let sp = self.0.savepoint().convert()?;
+        sp.execute(
+            "INSERT INTO items(pn, name, version, maturity, unit) VALUES(?1, ?2, ?3, ?4, ?5)",
+            (
+                &inner_item.pn,
+                &inner_item.name,
+                inner_item.version,
+                inner_item.maturity,
+                inner_item.unit,
+            ),
+        )
+        .convert_unique(|| Error::DuplicatePn(item.pn().to_string()))?;
+        let id = sp.last_insert_rowid();
+        sp.execute(
+            "INSERT INTO children (id_parent, id_child, quantity, unit)
+            SELECT ?1, id_child, quantity, unit FROM children WHERE id_parent = ?2",
+            (id, item._id),
+        )
+        .convert()?;
+        sp.execute(
+            "INSERT INTO attributes (id_item, key, type, value)
+            SELECT ?1, key, type, value FROM attributes WHERE id_item = ?2",
+            (id, item._id),
+        )
+        .convert()?;
+        sp.commit().convert()?;
+        Ok(Item::new(id as usize, inner_item))
+    }
+
+    /// Get the item, as currently saved in the database
+    pub(crate) fn reload_item(&self, item: &Item) -> Result<Item> {
+        let mut stmt = self
+            .0
+            .prepare("SELECT * FROM items WHERE id = ?1")
+            .convert()?;
+        stmt.query_row([item._id], |row| Item::try_from(row))
+            .convert_no_rows(|| Error::ItemNotFound(item.pn().to_string()))
+    }
+
     /// Update the item
     pub(crate) fn update_item(&mut self, item: Item) -> Result<()> {
-        if self
+        let changed = self
             .0
             .execute(
                 "UPDATE items set pn=(?1), name=(?2) where id=(?3)",
                 (&item.pn(), &item.name(), item._id),
             )
-            .convert()?
-            != 1
-        {
-            return Err(Error::DatabaseErr(rusqlite::Error::QueryReturnedNoRows));
-        }
-        Ok(())
+            .convert_unique(|| Error::DuplicatePn(item.pn().to_string()))?;
+        expect_one_row(changed, || Error::ItemNotFound(item.pn().to_string()))
     }
 
-    /// Get `Item` by it's PN
-    ///
-    /// WARNING : this function returns the 1st result (but there
-    /// should be only 1 result)
+    /// Change the unit of measure of the item
+    pub(crate) fn update_unit(&mut self, item: &Item, unit: Unit) -> Result<()> {
+        let changed = self
+            .0
+            .execute("UPDATE items set unit=(?1) where id=(?2)", (unit, item._id))
+            .convert()?;
+        expect_one_row(changed, || Error::ItemNotFound(item.pn().to_string()))
+    }
+
+    /// Update the maturity of the item
+    pub(crate) fn update_maturity(&mut self, item: &Item, maturity: ItemMaturity) -> Result<()> {
+        let changed = self
+            .0
+            .execute(
+                "UPDATE items set maturity=(?1) where id=(?2)",
+                (maturity, item._id),
+            )
+            .convert()?;
+        expect_one_row(changed, || Error::ItemNotFound(item.pn().to_string()))
+    }
+
+    /// Get the last revision of an `Item` by it's PN
     pub fn get_item_by_pn(&self, pn: &str) -> Result<Item> {
         let mut stmt = self
             .0
-            .prepare("SELECT * FROM items WHERE pn = ?1")
+            .prepare("SELECT * FROM items WHERE pn = ?1 ORDER BY version DESC LIMIT 1")
             .convert()?;
-        stmt.query_row([pn], |row| Item::try_from(row)).convert()
+        stmt.query_row([pn], |row| Item::try_from(row))
+            .convert_no_rows(|| Error::ItemNotFound(pn.to_string()))
+    }
+
+    /// Get a given revision of an `Item`
+    pub fn get_item(&self, pn: &str, version: usize) -> Result<Item> {
+        let mut stmt = self
+            .0
+            .prepare("SELECT * FROM items WHERE pn = ?1 AND version = ?2")
+            .convert()?;
+        stmt.query_row((pn, version), |row| Item::try_from(row))
+            .convert_no_rows(|| Error::RevisionNotFound {
+                pn: pn.to_string(),
+                version,
+            })
     }
 
     /// Add a child to an item
-    pub(crate) fn add_child(&mut self, parent: &Item, child: &Item, quantity: usize) -> Result<()> {
-        if self
+    pub(crate) fn add_child(
+        &mut self,
+        parent: &Item,
+        child: &Item,
+        quantity: Quantity,
+    ) -> Result<()> {
+        self.0
+            .execute(
+                "INSERT INTO children (id_parent, id_child, quantity, unit) VALUES(?1, ?2, ?3, ?4)",
+                (parent._id, child._id, quantity.value, quantity.unit),
+            )
+            .convert_unique(|| Error::ChildAlreadyLinked {
+                pn: parent.pn().to_string(),
+                child_pn: child.pn().to_string(),
+            })?;
+        Ok(())
+    }
+
+    /// Remove a child from an item
+    pub(crate) fn remove_child(&mut self, parent: &Item, child: &Item) -> Result<()> {
+        let changed = self
             .0
             .execute(
-                "INSERT INTO children (id_parent, id_child, quantity) VALUES(?1, ?2, ?3)",
-                (parent._id, child._id, quantity),
+                "DELETE FROM children WHERE id_parent = ?1 AND id_child = ?2",
+                (parent._id, child._id),
             )
+            .convert()?;
+        expect_one_row(changed, || Error::ChildNotLinked {
+            pn: parent.pn().to_string(),
+            child_pn: child.pn().to_string(),
+        })
+    }
+
+    /// Change the quantity of a child in an item
+    pub(crate) fn update_child_quantity(
+        &mut self,
+        parent: &Item,
+        child: &Item,
+        quantity: Quantity,
+    ) -> Result<()> {
+        let changed = self
+            .0
+            .execute(
+                "UPDATE children SET quantity = ?3, unit = ?4
+                WHERE id_parent = ?1 AND id_child = ?2",
+                (parent._id, child._id, quantity.value, quantity.unit),
+            )
+            .convert()?;
+        expect_one_row(changed, || Error::ChildNotLinked {
+            pn: parent.pn().to_string(),
+            child_pn: child.pn().to_string(),
+        })
+    }
+
+    /// Get the attributes of an item
+    pub(crate) fn get_attributes(&self, item: &Item) -> Result<Attributes> {
+        let mut stmt = self
+            .0
+            .prepare("SELECT key, type, value FROM attributes WHERE id_item = ?1")
+            .convert()?;
+        let rows = stmt
+            .query_map([item._id], |row| {
+                let kind: AttributeType = row.get("type")?;
+                let value: String = row.get("value")?;
+                Ok((row.get("key")?, kind, value))
+            })
             .convert()?
-            != 1
-        {
-            return Err(Error::DatabaseErr(rusqlite::Error::QueryReturnedNoRows));
-        }
+            .collect::<rusqlite::Result<Vec<(String, _, _)>>>()
+            .convert()?;
+        rows.into_iter()
+            .map(|(key, kind, value)| Ok((key, AttributeValue::parse(kind, &value)?)))
+            .collect()
+    }
+
+    /// Set an attribute of an item, replacing its previous value
+    pub(crate) fn set_attribute(
+        &mut self,
+        item: &Item,
+        key: &str,
+        value: &AttributeValue,
+    ) -> Result<()> {
+        self.0
+            .execute(
+                "REPLACE INTO attributes (id_item, key, type, value) VALUES(?1, ?2, ?3, ?4)",
+                (item._id, key, value.kind(), value.to_string()),
+            )
+            .convert()?;
         Ok(())
     }
 
+    /// Remove an attribute from an item
+    pub(crate) fn delete_attribute(&mut self, item: &Item, key: &str) -> Result<()> {
+        let changed = self
+            .0
+            .execute(
+                "DELETE FROM attributes WHERE id_item = ?1 AND key = ?2",
+                (item._id, key),
+            )
+            .convert()?;
+        expect_one_row(changed, || Error::AttributeNotFound {
+            pn: item.pn().to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    /// Delete an item, with its attributes and all the links to its children
+    /// and parents
+    pub(crate) fn delete_item(&mut self, item: &Item) -> Result<()> {
+        let sp = self.0.savepoint().convert()?;
+        sp.execute(
+            "DELETE FROM children WHERE id_parent = ?1 OR id_child = ?1",
+            [item._id],
+        )
+        .convert()?;
+        sp.execute("DELETE FROM attributes WHERE id_item = ?1", [item._id])
+            .convert()?;
+        let changed = sp
+            .execute("DELETE FROM items WHERE id = ?1", [item._id])
+            .convert()?;
+        expect_one_row(changed, || Error::ItemNotFound(item.pn().to_string()))?;
+        sp.commit().convert()
+    }
+
     /// Get children of an item
-    pub(crate) fn get_children(&self, parent: &Item) -> Result<Vec<(Item, usize)>> {
+    pub(crate) fn get_children(&self, parent: &Item) -> Result<Vec<(Item, Quantity)>> {
         let mut stmt = self
             .0
             .prepare("SELECT * FROM view_children WHERE id_parent = ?1")
@@ -259,7 +660,7 @@ impl Database {
         let items = stmt
             .query_map([parent._id], |row| {
                 let item = Item::try_from(row)?;
-                let quantity = row.get("quantity")?;
+                let quantity = Quantity::new(row.get("quantity")?, row.get("link_unit")?);
                 Ok((item, quantity))
             })
             .convert()?
@@ -268,7 +669,7 @@ impl Database {
         Ok(items)
     }
 
-    ///
+    /// Get all parent items using the given item
     pub(crate) fn where_used(&self, item: &Item) -> Result<Vec<Item>> {
         let mut stmt = self
             .0
@@ -281,12 +682,38 @@ impl Database {
             .collect::<Vec<_>>();
         Ok(items)
     }
+
+    /// Get the parents of an item, with the quantity of the item in each
+    /// parent
+    pub(crate) fn get_parents(&self, child: &Item) -> Result<Vec<(Item, Quantity)>> {
+        let mut stmt = self
+            .0
+            .prepare(
+                "SELECT items.*, children.quantity, children.unit AS link_unit FROM items, children
+                WHERE children.id_parent = items.id AND children.id_child = ?1",
+            )
+            .convert()?;
+        let items = stmt
+            .query_map([child._id], |row| {
+                let item = Item::try_from(row)?;
+                let quantity = Quantity::new(row.get("quantity")?, row.get("link_unit")?);
+                Ok((item, quantity))
+            })
+            .convert()?
+            .filter_map(|i| i.ok())
+            .collect::<Vec<_>>();
+        Ok(items)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn each(value: f64) -> Quantity {
+        Quantity::new(value, Unit::Each)
+    }
+
     #[test]
     fn init_database() {
         assert!(Database::open(":memory:").is_ok());
@@ -308,20 +735,109 @@ mod test {
         let item1 = db.insert_item("1", "PARENT").unwrap();
         let item2 = db.insert_item("11", "CHILD1").unwrap();
         let item3 = db.insert_item("12", "CHILD2").unwrap();
-        db.add_child(&item1, &item2, 1).unwrap();
-        db.add_child(&item1, &item3, 2).unwrap();
+        db.add_child(&item1, &item2, each(1.0)).unwrap();
+        db.add_child(&item1, &item3, each(2.0)).unwrap();
         let children = db.get_children(&item1).unwrap();
         assert_eq!(2, children.len());
 
         // can't add an already existing child
-        assert!(db.add_child(&item1, &item3, 2).is_err());
+        assert!(matches!(
+            db.add_child(&item1, &item3, each(2.0)),
+            Err(Error::ChildAlreadyLinked { pn, child_pn }) if pn == "1" && child_pn == "12"
+        ));
+
+        let parents = db.get_parents(&item3).unwrap();
+        assert_eq!(1, parents.len());
+        assert_eq!("1", parents[0].0.pn());
+        assert_eq!(each(2.0), parents[0].1);
+        assert!(db.get_parents(&item1).unwrap().is_empty());
     }
 
     #[test]
     fn add_same_pn() {
         let db = Database::open(":memory:").unwrap();
         let _ = db.insert_item("PN", "ITEM").unwrap();
-        assert!(db.insert_item("PN", "ANOTHER").is_err());
+        assert!(matches!(
+            db.insert_item("PN", "ANOTHER"),
+            Err(Error::DuplicatePn(pn)) if pn == "PN"
+        ));
+    }
+
+    #[test]
+    fn remove_childrens() {
+        let mut db = Database::open(":memory:").unwrap();
+        let parent = db.insert_item("1", "PARENT").unwrap();
+        let child1 = db.insert_item("11", "CHILD1").unwrap();
+        let child2 = db.insert_item("12", "CHILD2").unwrap();
+        db.add_child(&parent, &child1, each(1.0)).unwrap();
+        db.add_child(&parent, &child2, each(2.0)).unwrap();
+
+        db.update_child_quantity(&parent, &child2, each(5.0))
+            .unwrap();
+        let children = db.get_children(&parent).unwrap();
+        assert!(children.contains(&(child2.clone(), each(5.0))));
+
+        db.remove_child(&parent, &child1).unwrap();
+        assert!(matches!(
+            db.remove_child(&parent, &child1),
+            Err(Error::ChildNotLinked { .. })
+        ));
+        assert!(db
+            .update_child_quantity(&parent, &child1, each(1.0))
+            .is_err());
+        assert_eq!(1, db.get_children(&parent).unwrap().len());
+
+        db.delete_item(&child2).unwrap();
+        assert!(db.get_children(&parent).unwrap().is_empty());
+        assert!(matches!(
+            db.get_item_by_pn("12"),
+            Err(Error::ItemNotFound(pn)) if pn == "12"
+        ));
+        assert!(db.delete_item(&child2).is_err());
+    }
+
+    #[test]
+    fn revisions() {
+        let mut db = Database::open(":memory:").unwrap();
+        let parent = db.insert_item("1", "PARENT").unwrap();
+        let child = db.insert_item("11", "CHILD").unwrap();
+        db.update_unit(&parent, Unit::Metre).unwrap();
+        let parent = db.reload_item(&parent).unwrap();
+        let length = Quantity::new(300.0, Unit::Millimetre);
+        db.add_child(&parent, &child, length).unwrap();
+
+        let revision = db.insert_revision(&parent).unwrap();
+        assert_eq!(2, revision.version());
+        assert_eq!("PARENT", revision.name());
+        assert_eq!(ItemMaturity::InProgress, revision.maturity());
+        assert_eq!(Unit::Metre, revision.unit());
+        assert_eq!(
+            vec![(child.clone(), length)],
+            db.get_children(&revision).unwrap()
+        );
+
+        assert_eq!(2, db.get_item_by_pn("1").unwrap().version());
+        assert_eq!(parent, db.get_item("1", 1).unwrap());
+        assert_eq!(2, db.get_revisions("1").unwrap().len());
+        assert!(matches!(
+            db.get_item("1", 3),
+            Err(Error::RevisionNotFound { version: 3, .. })
+        ));
+        assert_eq!(2, db.get_items().unwrap().len());
+    }
+
+    #[test]
+    fn maturity() {
+        let mut db = Database::open(":memory:").unwrap();
+        let item = db.insert_item("PN", "ITEM").unwrap();
+        assert_eq!(ItemMaturity::InProgress, item.maturity());
+        db.update_maturity(&item, ItemMaturity::Obsolete).unwrap();
+        let item = db.get_item_by_pn("PN").unwrap();
+        assert_eq!(ItemMaturity::Obsolete, item.maturity());
+
+        db.0.execute("UPDATE items set maturity=42 where pn='PN'", ())
+            .unwrap();
+        assert!(db.get_item_by_pn("PN").is_err());
     }
 
     #[test]
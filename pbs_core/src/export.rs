@@ -0,0 +1,420 @@
+//! Export of the product structure to other tools, such as spreadsheets
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io::{Seek, Write},
+};
+
+use rust_xlsxwriter::{Format, Workbook};
+
+use crate::{
+    format_value, AttributeValue, Attributes, Error, Item, ItemMaturity, Quantity, Result, Store,
+    TreeNode, Unit,
+};
+
+/// A line of an indented BOM
+struct BomLine<'a> {
+    level: usize,
+    pn: &'a str,
+    version: usize,
+    name: &'a str,
+    maturity: String,
+    qty: f64,
+    extended_qty: f64,
+    unit: Unit,
+    attributes: &'a Attributes,
+}
+
+impl<'a> BomLine<'a> {
+    fn new(node: &'a TreeNode, attributes: &'a Attributes) -> Result<Self> {
+        Ok(BomLine {
+            level: node.level,
+            pn: node.item.pn(),
+            version: node.item.version(),
+            name: node.item.name(),
+            maturity: node.item.maturity().to_string(),
+            qty: node.quantity.to(node.item.unit())?,
+            extended_qty: node.total_quantity,
+            unit: node.item.unit(),
+            attributes,
+        })
+    }
+
+    /// The line as a CSV record, with the given attributes
+    fn record(&self, keys: &BTreeSet<String>) -> Vec<String> {
+        let mut record = vec![
+            self.level.to_string(),
+            self.pn.to_string(),
+            self.version.to_string(),
+            self.name.to_string(),
+            self.maturity.clone(),
+            format_value(self.qty),
+            format_value(self.extended_qty),
+            self.unit.to_string(),
+        ];
+        record.extend(keys.iter().map(|key| {
+            self.attributes
+                .get(key)
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        }));
+        record
+    }
+}
+
+/// The multi-level BOM of an item, with the attributes of its items
+struct Bom {
+    tree: TreeNode,
+    attributes: HashMap<Item, Attributes>,
+    /// Keys of all the attributes, which are the last columns of the BOM
+    keys: BTreeSet<String>,
+}
+
+impl Bom {
+    fn new(store: &Store, pn: &str) -> Result<Self> {
+        let tree = store.get_tree(pn, None)?;
+        let mut attributes = HashMap::new();
+        for node in tree.iter() {
+            if !attributes.contains_key(&node.item) {
+                attributes.insert(node.item.clone(), store.get_attributes(&node.item)?);
+            }
+        }
+        let keys = attributes
+            .values()
+            .flat_map(|a| a.keys().cloned())
+            .collect();
+        Ok(Bom {
+            tree,
+            attributes,
+            keys,
+        })
+    }
+
+    fn columns(&self) -> impl Iterator<Item = &str> {
+        BOM_COLUMNS
+            .into_iter()
+            .chain(self.keys.iter().map(String::as_str))
+    }
+
+    fn lines(&self) -> impl Iterator<Item = Result<BomLine<'_>>> {
+        self.tree
+            .iter()
+            .map(|node| BomLine::new(node, &self.attributes[&node.item]))
+    }
+}
+
+/// Titles of the columns of an indented BOM
+const BOM_COLUMNS: [&str; 8] = [
+    "level",
+    "pn",
+    "version",
+    "name",
+    "maturity",
+    "qty",
+    "extended_qty",
+    "unit",
+];
+
+/// Maximum indentation of a cell in a workbook
+const MAX_INDENT: usize = 15;
+
+/// Format of a graph of the product structure
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT, to be rendered with e.g. `dot -Tsvg`
+    Dot,
+    /// Mermaid flowchart, which can be embedded in Markdown documents
+    Mermaid,
+}
+
+/// Fill color of a node of a graph
+fn maturity_color(maturity: ItemMaturity) -> &'static str {
+    match maturity {
+        ItemMaturity::InProgress => "#fff2cc",
+        ItemMaturity::InReview => "#dae8fc",
+        ItemMaturity::Released => "#d5e8d4",
+        ItemMaturity::Obsolete => "#e0e0e0",
+    }
+}
+
+/// The product structure under an item, as a graph where an item used in
+/// several parents is a single node
+struct Graph {
+    nodes: Vec<Item>,
+    /// Parent node, child node and quantity of each link
+    edges: Vec<(usize, usize, Quantity)>,
+}
+
+impl Graph {
+    fn new(tree: &TreeNode) -> Self {
+        let mut graph = Graph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        };
+        let mut ids = HashMap::new();
+        let mut id = |item: &Item, nodes: &mut Vec<Item>| {
+            *ids.entry(item.clone()).or_insert_with(|| {
+                nodes.push(item.clone());
+                nodes.len() - 1
+            })
+        };
+        let mut done = HashSet::new();
+        for node in tree.iter() {
+            let parent = id(&node.item, &mut graph.nodes);
+            // the links of a shared item are only drawn for its first usage
+            if !done.insert(parent) {
+                continue;
+            }
+            for child in &node.children {
+                let child_id = id(&child.item, &mut graph.nodes);
+                graph.edges.push((parent, child_id, child.quantity));
+            }
+        }
+        graph
+    }
+
+    fn dot(&self, name: &str) -> String {
+        let escape = |label: String| label.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut lines = vec![
+            format!("digraph \"{}\" {{", escape(name.to_string())),
+            "    node [shape=box, style=\"rounded,filled\"];".to_string(),
+        ];
+        for (id, item) in self.nodes.iter().enumerate() {
+            lines.push(format!(
+                "    n{id} [label=\"{label}\", fillcolor=\"{color}\"];",
+                label = escape(item.to_string()),
+                color = maturity_color(item.maturity())
+            ));
+        }
+        for (parent, child, quantity) in &self.edges {
+            lines.push(format!("    n{parent} -> n{child} [label=\"{quantity}\"];"));
+        }
+        lines.push("}\n".to_string());
+        lines.join("\n")
+    }
+
+    fn mermaid(&self) -> String {
+        let mut lines = vec!["flowchart TD".to_string()];
+        for (id, item) in self.nodes.iter().enumerate() {
+            let label = item.to_string().replace('"', "#quot;");
+            lines.push(format!("    n{id}[\"{label}\"]"));
+        }
+        for (parent, child, quantity) in &self.edges {
+            lines.push(format!("    n{parent} -->|{quantity}| n{child}"));
+        }
+        for (id, item) in self.nodes.iter().enumerate() {
+            let color = maturity_color(item.maturity());
+            lines.push(format!("    style n{id} fill:{color}"));
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+impl Store {
+    /// Get the graph of the product structure under an item
+    ///
+    /// The nodes are labelled with the items and filled with a color
+    /// depending on their maturity, and the links are labelled with their
+    /// quantity. An item used in several parents is drawn once.
+    pub fn export_graph(&self, pn: &str, format: GraphFormat) -> Result<String> {
+        let tree = self.get_tree(pn, None)?;
+        let graph = Graph::new(&tree);
+        Ok(match format {
+            GraphFormat::Dot => graph.dot(pn),
+            GraphFormat::Mermaid => graph.mermaid(),
+        })
+    }
+
+    /// Write the multi-level BOM of an item as an indented CSV table
+    ///
+    /// The item is at level 0 and each line gives the level of the item in
+    /// the structure, its quantity in its parent and its extended quantity,
+    /// i.e. its quantity in the exported item along this branch, both in
+    /// the unit of the item given next. The attributes of the items follow,
+    /// one column per attribute key.
+    pub fn export_bom_csv(&self, pn: &str, writer: impl Write) -> Result<()> {
+        let bom = Bom::new(self, pn)?;
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(bom.columns()).map_err(Error::CsvErr)?;
+        for line in bom.lines() {
+            let line = line?;
+            writer
+                .write_record(line.record(&bom.keys))
+                .map_err(Error::CsvErr)?;
+        }
+        writer.flush().map_err(|e| Error::CsvErr(e.into()))
+    }
+
+    /// Write the multi-level BOM of an item as an .xlsx workbook
+    ///
+    /// The columns are the ones of [Store::export_bom_csv], and the PNs are
+    /// indented according to their level. Number and boolean attributes are
+    /// written as numbers and booleans.
+    pub fn export_bom_xlsx(&self, pn: &str, writer: impl Write + Seek + Send) -> Result<()> {
+        let bom = Bom::new(self, pn)?;
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("BOM").map_err(Error::XlsxErr)?;
+        let bold = Format::new().set_bold();
+        for (col, title) in bom.columns().enumerate() {
+            sheet
+                .write_string_with_format(0, col as u16, title, &bold)
+                .map_err(Error::XlsxErr)?;
+        }
+        for (row, line) in bom.lines().enumerate() {
+            let line = line?;
+            let row = row as u32 + 1;
+            let indent = Format::new().set_indent(line.level.min(MAX_INDENT) as u8);
+            sheet
+                .write_number(row, 0, line.level as f64)
+                .and_then(|s| s.write_string_with_format(row, 1, line.pn, &indent))
+                .and_then(|s| s.write_number(row, 2, line.version as f64))
+                .and_then(|s| s.write_string(row, 3, line.name))
+                .and_then(|s| s.write_string(row, 4, line.maturity))
+                .and_then(|s| s.write_number(row, 5, line.qty))
+                .and_then(|s| s.write_number(row, 6, line.extended_qty))
+                .and_then(|s| s.write_string(row, 7, line.unit.symbol()))
+                .map_err(Error::XlsxErr)?;
+            for (col, key) in bom.keys.iter().enumerate() {
+                let col = (BOM_COLUMNS.len() + col) as u16;
+                match line.attributes.get(key) {
+                    Some(AttributeValue::Number(number)) => sheet.write_number(row, col, *number),
+                    Some(AttributeValue::Bool(value)) => sheet.write_boolean(row, col, *value),
+                    Some(value) => sheet.write_string(row, col, value.to_string()),
+                    None => continue,
+                }
+                .map_err(Error::XlsxErr)?;
+            }
+        }
+        sheet.set_freeze_panes(1, 0).map_err(Error::XlsxErr)?;
+        sheet.autofit();
+        workbook.save_to_writer(writer).map_err(Error::XlsxErr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn bike() -> Store {
+        let mut store = Store::open(":memory:").unwrap();
+        store.new_item("1", "bike").unwrap();
+        store.new_item("2", "wheel").unwrap();
+        store.new_item("3", "spoke").unwrap();
+        store.new_item("4", "frame").unwrap();
+        store.add_child("1", "2", 2.0).unwrap();
+        store.add_child("2", "3", 36.0).unwrap();
+        store.add_child("1", "4", 1.0).unwrap();
+        store
+    }
+
+    #[test]
+    fn bom_csv() {
+        let store = bike();
+        let mut csv = Vec::new();
+        store.export_bom_csv("1", &mut csv).unwrap();
+        assert_eq!(
+            "level,pn,version,name,maturity,qty,extended_qty,unit
+0,1,1,bike,In progress...,1,1,each
+1,2,1,wheel,In progress...,2,2,each
+2,3,1,spoke,In progress...,36,72,each
+1,4,1,frame,In progress...,1,1,each
+",
+            String::from_utf8(csv).unwrap()
+        );
+        assert!(store.export_bom_csv("5", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn bom_attributes() {
+        let mut store = bike();
+        let text = |t: &str| AttributeValue::Text(t.to_string());
+        store
+            .set_attribute("3", "material", &text("steel"))
+            .unwrap();
+        store
+            .set_attribute("4", "material", &text("aluminium"))
+            .unwrap();
+        store
+            .set_attribute("4", "weight", &AttributeValue::Number(1.6))
+            .unwrap();
+        let mut csv = Vec::new();
+        store.export_bom_csv("1", &mut csv).unwrap();
+        assert_eq!(
+            "level,pn,version,name,maturity,qty,extended_qty,unit,material,weight
+0,1,1,bike,In progress...,1,1,each,,
+1,2,1,wheel,In progress...,2,2,each,,
+2,3,1,spoke,In progress...,36,72,each,steel,
+1,4,1,frame,In progress...,1,1,each,aluminium,1.6
+",
+            String::from_utf8(csv).unwrap()
+        );
+        let mut xlsx = Cursor::new(Vec::new());
+        store.export_bom_xlsx("1", &mut xlsx).unwrap();
+    }
+
+    #[test]
+    fn graph() {
+        let mut store = bike();
+        // the spoke is also used directly in the bike, as a spare part
+        store.add_child("1", "3", 2.0).unwrap();
+        store.set_maturity("3", ItemMaturity::Released).unwrap();
+        assert_eq!(
+            r##"digraph "1" {
+    node [shape=box, style="rounded,filled"];
+    n0 [label="[1-001] \"bike\" - In progress...", fillcolor="#fff2cc"];
+    n1 [label="[2-001] \"wheel\" - In progress...", fillcolor="#fff2cc"];
+    n2 [label="[3-001] \"spoke\" - Released", fillcolor="#d5e8d4"];
+    n3 [label="[4-001] \"frame\" - In progress...", fillcolor="#fff2cc"];
+    n0 -> n1 [label="2"];
+    n0 -> n2 [label="2"];
+    n0 -> n3 [label="1"];
+    n1 -> n2 [label="36"];
+}
+"##,
+            store.export_graph("1", GraphFormat::Dot).unwrap()
+        );
+        assert_eq!(
+            r##"flowchart TD
+    n0["[2-001] #quot;wheel#quot; - In progress..."]
+    n1["[3-001] #quot;spoke#quot; - Released"]
+    n0 -->|36| n1
+    style n0 fill:#fff2cc
+    style n1 fill:#d5e8d4
+"##,
+            store.export_graph("2", GraphFormat::Mermaid).unwrap()
+        );
+    }
+
+    #[test]
+    fn bom_units() {
+        let mut store = bike();
+        // the brake cable is counted in metres, and cut in 1.2 m lengths
+        store.new_item("5", "brake cable").unwrap();
+        store.set_unit("5", Unit::Metre).unwrap();
+        store
+            .add_child_in("2", "5", 1200.0, Unit::Millimetre)
+            .unwrap();
+        let mut csv = Vec::new();
+        store.export_bom_csv("1", &mut csv).unwrap();
+        assert!(String::from_utf8(csv)
+            .unwrap()
+            .contains("\n2,5,1,brake cable,In progress...,1.2,2.4,m\n"));
+        assert!(store
+            .export_graph("2", GraphFormat::Mermaid)
+            .unwrap()
+            .contains("-->|1200 mm|"));
+    }
+
+    #[test]
+    fn bom_xlsx() {
+        let store = bike();
+        let mut xlsx = Cursor::new(Vec::new());
+        store.export_bom_xlsx("1", &mut xlsx).unwrap();
+        // an .xlsx file is a zip archive
+        assert!(xlsx.into_inner().starts_with(b"PK"));
+    }
+}
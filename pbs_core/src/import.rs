@@ -0,0 +1,620 @@
+//! Import of items and links from CSV files, such as BOMs exported from
+//! spreadsheets
+
+use std::io::Read;
+
+use csv::StringRecord;
+
+use crate::{
+    parse_link_quantity, AttributeValue, Attributes, Error, Item, Quantity, Result, Store, Unit,
+};
+
+/// Names of the CSV columns of each imported field
+///
+/// By default, the columns are named after the fields, e.g. `pn` or
+/// `parent_pn`. The other columns of the items table are attributes of the
+/// items.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportColumns {
+    /// PN of an item, in the items table
+    pub pn: String,
+    /// Name of an item, in the items table
+    pub name: String,
+    /// PN of the parent item, in the links table
+    pub parent_pn: String,
+    /// PN of the child item, in the links table
+    pub child_pn: String,
+    /// Quantity of the child in the parent, in the links table, with an
+    /// optional unit, e.g. `2.5 m`
+    pub quantity: String,
+}
+
+impl Default for ImportColumns {
+    fn default() -> Self {
+        ImportColumns {
+            pn: "pn".to_string(),
+            name: "name".to_string(),
+            parent_pn: "parent_pn".to_string(),
+            child_pn: "child_pn".to_string(),
+            quantity: "quantity".to_string(),
+        }
+    }
+}
+
+/// The kind of table of an imported CSV file
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CsvTable {
+    /// One item per row
+    Items,
+    /// One parent/child link per row
+    Links,
+}
+
+impl std::fmt::Display for CsvTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsvTable::Items => write!(f, "items"),
+            CsvTable::Links => write!(f, "links"),
+        }
+    }
+}
+
+/// The change made in the store by an imported row
+#[derive(Debug)]
+pub enum ImportChange {
+    /// The item didn't exist and was created, with the attributes of its
+    /// row
+    Created { item: Item, attributes: Attributes },
+    /// The item existed with another name or other attribute values : the
+    /// old name if it was renamed, and the attributes which were set
+    Updated {
+        item: Item,
+        old_name: Option<String>,
+        attributes: Attributes,
+    },
+    /// The child was added to the parent
+    Linked {
+        parent_pn: String,
+        child_pn: String,
+        quantity: Quantity,
+    },
+    /// The child was already linked to the parent with another quantity
+    QuantityChanged {
+        parent_pn: String,
+        child_pn: String,
+        old_quantity: Quantity,
+        quantity: Quantity,
+    },
+    /// The store was already up to date
+    Unchanged,
+}
+
+impl std::fmt::Display for ImportChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportChange::Created { item, attributes } if attributes.is_empty() => {
+                write!(f, "create {item}")
+            }
+            ImportChange::Created { item, attributes } => {
+                write!(f, "create {item} with {}", attributes_text(attributes))
+            }
+            ImportChange::Updated {
+                item,
+                old_name,
+                attributes,
+            } => {
+                let mut changes = Vec::new();
+                if let Some(old_name) = old_name {
+                    changes.push(format!(
+                        "rename {pn} from \"{old_name}\" to \"{name}\"",
+                        pn = item.pn(),
+                        name = item.name()
+                    ));
+                }
+                if !attributes.is_empty() {
+                    changes.push(format!(
+                        "set {} on {}",
+                        attributes_text(attributes),
+                        item.pn()
+                    ));
+                }
+                write!(f, "{}", changes.join(", "))
+            }
+            ImportChange::Linked {
+                parent_pn,
+                child_pn,
+                quantity,
+            } => write!(f, "add {child_pn} to {parent_pn} : {quantity}"),
+            ImportChange::QuantityChanged {
+                parent_pn,
+                child_pn,
+                old_quantity,
+                quantity,
+            } => write!(
+                f,
+                "change the quantity of {child_pn} in {parent_pn} from {old_quantity} to {quantity}"
+            ),
+            ImportChange::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+/// Attributes written as `key=value` pairs, e.g. `supplier=ACME, color=red`
+fn attributes_text(attributes: &Attributes) -> String {
+    attributes
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The result of the import of a row
+#[derive(Debug)]
+pub struct ImportLine {
+    pub table: CsvTable,
+    /// Line of the row in its CSV file, the header being the line 1
+    pub line: u64,
+    pub result: Result<ImportChange>,
+}
+
+/// Report of a CSV import, with the result of every row
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub lines: Vec<ImportLine>,
+    /// The import was only a dry run, nothing was saved
+    pub dry_run: bool,
+}
+
+impl ImportReport {
+    /// The rows which couldn't be imported
+    pub fn errors(&self) -> impl Iterator<Item = &ImportLine> {
+        self.lines.iter().filter(|line| line.result.is_err())
+    }
+
+    /// The rows which changed the store
+    pub fn changes(&self) -> impl Iterator<Item = (&ImportLine, &ImportChange)> {
+        self.lines.iter().filter_map(|line| match &line.result {
+            Ok(ImportChange::Unchanged) | Err(_) => None,
+            Ok(change) => Some((line, change)),
+        })
+    }
+}
+
+/// Get the index of each column in the `headers` of a CSV file
+fn column_indexes(headers: &StringRecord, columns: &[&str]) -> Result<Vec<usize>> {
+    columns
+        .iter()
+        .map(|column| {
+            headers
+                .iter()
+                .position(|header| header == *column)
+                .ok_or_else(|| Error::MissingColumn(column.to_string()))
+        })
+        .collect()
+}
+
+/// Get the values of a row, in the order of the `columns`
+///
+/// All the values are required, so an empty value is invalid.
+fn row_values<'r>(
+    record: &'r StringRecord,
+    indexes: &[usize],
+    columns: &[&str],
+) -> Result<Vec<&'r str>> {
+    indexes
+        .iter()
+        .zip(columns)
+        .map(|(&index, column)| match record.get(index) {
+            Some(value) if !value.is_empty() => Ok(value),
+            value => Err(Error::InvalidValue {
+                column: column.to_string(),
+                value: value.unwrap_or_default().to_string(),
+            }),
+        })
+        .collect()
+}
+
+/// Get the attributes of a row, from the attribute columns given by their
+/// index and name, ignoring the empty values
+fn row_attributes(record: &StringRecord, columns: &[(usize, String)]) -> Attributes {
+    columns
+        .iter()
+        .filter_map(|(index, column)| match record.get(*index) {
+            Some(value) if !value.is_empty() => {
+                Some((column.clone(), AttributeValue::Text(value.to_string())))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+impl Store {
+    /// Import items and links from CSV files
+    ///
+    /// Each row of the `items` table creates an item, or renames it if it
+    /// exists with another name. The other columns of the `items` table are
+    /// attributes of the items, set when they are not empty : the new
+    /// attributes are text, and the existing ones keep their type. Each row
+    /// of the `links` table adds a child to a parent, or changes its
+    /// quantity if it is already linked. The items are imported first, so
+    /// the links can use them. The columns are found with their names in the
+    /// header line, other columns of the `links` table are ignored.
+    ///
+    /// A failing row doesn't stop the import, its error is given in the
+    /// report. Everything is imported in a single transaction, which is
+    /// only committed if all the rows are imported and it is not a
+    /// `dry_run`. On a dry run, the report tells what the import would do.
+    ///
+    /// Fails if a file can't be read or doesn't have a column, and with
+    /// [Error::ImportFailed] if some rows can't be imported.
+    pub fn import_csv(
+        &mut self,
+        items: Option<impl Read>,
+        links: Option<impl Read>,
+        columns: &ImportColumns,
+        dry_run: bool,
+    ) -> Result<ImportReport> {
+        let mut report = ImportReport {
+            lines: Vec::new(),
+            dry_run,
+        };
+        self.begin_transaction()?;
+        let mut result = Ok(());
+        if let Some(items) = items {
+            result = self.import_table(CsvTable::Items, items, columns, &mut report);
+        }
+        if let (Ok(()), Some(links)) = (&result, links) {
+            result = self.import_table(CsvTable::Links, links, columns, &mut report);
+        }
+        if result.is_err() || dry_run {
+            self.rollback_transaction()?;
+            return result.map(|_| report);
+        }
+        if report.errors().next().is_some() {
+            self.rollback_transaction()?;
+            return Err(Error::ImportFailed(report));
+        }
+        self.commit_transaction()?;
+        Ok(report)
+    }
+
+    /// Import all the rows of a CSV table, adding their result to the
+    /// `report`
+    fn import_table(
+        &mut self,
+        table: CsvTable,
+        csv: impl Read,
+        columns: &ImportColumns,
+        report: &mut ImportReport,
+    ) -> Result<()> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(csv);
+        let columns = match table {
+            CsvTable::Items => vec![columns.pn.as_str(), columns.name.as_str()],
+            CsvTable::Links => vec![
+                columns.parent_pn.as_str(),
+                columns.child_pn.as_str(),
+                columns.quantity.as_str(),
+            ],
+        };
+        let headers = reader.headers().map_err(Error::CsvErr)?;
+        let indexes = column_indexes(headers, &columns)?;
+        let attribute_columns = match table {
+            CsvTable::Items => headers
+                .iter()
+                .enumerate()
+                .filter(|(_, header)| !header.is_empty() && !columns.contains(header))
+                .map(|(index, header)| (index, header.to_string()))
+                .collect(),
+            CsvTable::Links => Vec::new(),
+        };
+        for record in reader.records() {
+            let (line, result) = match record {
+                Ok(record) => (
+                    record.position().map_or(0, |p| p.line()),
+                    row_values(&record, &indexes, &columns).and_then(|values| {
+                        let attributes = row_attributes(&record, &attribute_columns);
+                        self.import_row(table, &values, attributes, &columns)
+                    }),
+                ),
+                Err(e) => (e.position().map_or(0, |p| p.line()), Err(Error::CsvErr(e))),
+            };
+            report.lines.push(ImportLine {
+                table,
+                line,
+                result,
+            });
+        }
+        Ok(())
+    }
+
+    fn import_row(
+        &mut self,
+        table: CsvTable,
+        values: &[&str],
+        attributes: Attributes,
+        columns: &[&str],
+    ) -> Result<ImportChange> {
+        match table {
+            CsvTable::Items => self.import_item(values[0], values[1], attributes),
+            CsvTable::Links => {
+                let (quantity, unit) =
+                    parse_link_quantity(values[2]).map_err(|_| Error::InvalidValue {
+                        column: columns[2].to_string(),
+                        value: values[2].to_string(),
+                    })?;
+                self.import_link(values[0], values[1], quantity, unit)
+            }
+        }
+    }
+
+    /// Import an item, only setting the attributes which have another
+    /// value
+    ///
+    /// The new attributes are text, while the values of the existing ones
+    /// are parsed with their type, failing if they are invalid.
+    fn import_item(
+        &mut self,
+        pn: &str,
+        name: &str,
+        attributes: Attributes,
+    ) -> Result<ImportChange> {
+        let mut item = match self.get_item_by_pn(pn) {
+            Ok(item) => item,
+            Err(Error::ItemNotFound(_)) => {
+                let item = self.new_item(pn, name)?;
+                for (key, value) in &attributes {
+                    self.set_attribute(pn, key, value)?;
+                }
+                return Ok(ImportChange::Created { item, attributes });
+            }
+            Err(e) => return Err(e),
+        };
+        let current = self.get_attributes(&item)?;
+        let mut changed = Attributes::new();
+        for (key, value) in attributes {
+            let value = match current.get(&key) {
+                Some(old) => AttributeValue::parse(old.kind(), &value.to_string())?,
+                None => value,
+            };
+            if current.get(&key) != Some(&value) {
+                changed.insert(key, value);
+            }
+        }
+        let attributes = changed;
+        let old_name = (item.name() != name).then(|| item.name().to_string());
+        if old_name.is_none() && attributes.is_empty() {
+            return Ok(ImportChange::Unchanged);
+        }
+        if old_name.is_some() {
+            item.set_name(name);
+            self.save_item(item.clone())?;
+        }
+        for (key, value) in &attributes {
+            self.set_attribute(pn, key, value)?;
+        }
+        Ok(ImportChange::Updated {
+            item,
+            old_name,
+            attributes,
+        })
+    }
+
+    /// Import a link, the quantity being in the unit of the existing link
+    /// or of the child when no `unit` is given
+    fn import_link(
+        &mut self,
+        parent_pn: &str,
+        child_pn: &str,
+        value: f64,
+        unit: Option<Unit>,
+    ) -> Result<ImportChange> {
+        let children = self.get_children(parent_pn)?;
+        match children.iter().find(|(child, _)| child.pn() == child_pn) {
+            Some((_, old_quantity)) => {
+                let quantity = Quantity::new(value, unit.unwrap_or(old_quantity.unit));
+                if *old_quantity == quantity {
+                    return Ok(ImportChange::Unchanged);
+                }
+                self.set_child_quantity_in(parent_pn, child_pn, quantity.value, quantity.unit)?;
+                Ok(ImportChange::QuantityChanged {
+                    parent_pn: parent_pn.to_string(),
+                    child_pn: child_pn.to_string(),
+                    old_quantity: *old_quantity,
+                    quantity,
+                })
+            }
+            None => {
+                let child = self.get_item_by_pn(child_pn)?;
+                let quantity = Quantity::new(value, unit.unwrap_or(child.unit()));
+                self.add_child_in(parent_pn, child_pn, quantity.value, quantity.unit)?;
+                Ok(ImportChange::Linked {
+                    parent_pn: parent_pn.to_string(),
+                    child_pn: child_pn.to_string(),
+                    quantity,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ITEMS: &str = "pn,name,supplier
+1,chair,ACME
+2,table,ACME
+3,room,
+1,chair,ACME
+";
+
+    const LINKS: &str = "parent_pn,child_pn,quantity
+3,1,25
+3,2,x
+3,4,1
+";
+
+    fn changes(report: &ImportReport) -> Vec<String> {
+        report
+            .lines
+            .iter()
+            .map(|line| match &line.result {
+                Ok(change) => format!("{} {} : {}", line.table, line.line, change),
+                Err(e) => format!("{} {} : ERROR {}", line.table, line.line, e),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn import() {
+        let mut store = Store::open(":memory:").unwrap();
+        let columns = ImportColumns::default();
+        let report = store
+            .import_csv(Some(ITEMS.as_bytes()), None::<&[u8]>, &columns, false)
+            .unwrap();
+        assert_eq!(
+            vec![
+                "items 2 : create [1-001] \"chair\" - In progress... with supplier=ACME",
+                "items 3 : create [2-001] \"table\" - In progress... with supplier=ACME",
+                "items 4 : create [3-001] \"room\" - In progress...",
+                "items 5 : unchanged",
+            ],
+            changes(&report)
+        );
+        assert_eq!(3, store.get_items().unwrap().len());
+        let chair = store.get_item_by_pn("1").unwrap();
+        assert_eq!(
+            Some(&AttributeValue::Text("ACME".to_string())),
+            store.get_attributes(&chair).unwrap().get("supplier")
+        );
+
+        let items = "pn,name\n1,armchair\n";
+        let links = "parent_pn,child_pn,quantity\n3,1,25\n3,2,25\n";
+        let report = store
+            .import_csv(
+                Some(items.as_bytes()),
+                Some(links.as_bytes()),
+                &columns,
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            vec![
+                "items 2 : rename 1 from \"chair\" to \"armchair\"",
+                "links 2 : add 1 to 3 : 25",
+                "links 3 : add 2 to 3 : 25",
+            ],
+            changes(&report)
+        );
+        assert_eq!(2, store.get_children("3").unwrap().len());
+
+        // a number attribute with the same value is kept with its type
+        let weight = AttributeValue::Number(2.5);
+        store.set_attribute("2", "weight", &weight).unwrap();
+        let items = "pn,name,supplier,weight,color\n1,chair,ACME,,red\n2,table,Globex,2.5,\n";
+        let report = store
+            .import_csv(Some(items.as_bytes()), None::<&[u8]>, &columns, false)
+            .unwrap();
+        assert_eq!(
+            vec![
+                "items 2 : rename 1 from \"armchair\" to \"chair\", set color=red on 1",
+                "items 3 : set supplier=Globex on 2",
+            ],
+            changes(&report)
+        );
+        let table = store.get_item_by_pn("2").unwrap();
+        assert_eq!(
+            Some(&weight),
+            store.get_attributes(&table).unwrap().get("weight")
+        );
+
+        // a new value of a number attribute is still a number
+        let items = "pn,name,weight\n2,table,3\n";
+        let report = store
+            .import_csv(Some(items.as_bytes()), None::<&[u8]>, &columns, false)
+            .unwrap();
+        assert_eq!(vec!["items 2 : set weight=3 on 2"], changes(&report));
+        assert_eq!(
+            Some(&AttributeValue::Number(3.0)),
+            store.get_attributes(&table).unwrap().get("weight")
+        );
+        let items = "pn,name,weight\n2,table,heavy\n";
+        let err = store
+            .import_csv(Some(items.as_bytes()), None::<&[u8]>, &columns, false)
+            .unwrap_err();
+        let Error::ImportFailed(report) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(
+            vec!["items 2 : ERROR invalid number \"heavy\""],
+            changes(&report)
+        );
+    }
+
+    #[test]
+    fn import_errors_cancel_everything() {
+        let mut store = Store::open(":memory:").unwrap();
+        let columns = ImportColumns::default();
+        let err = store
+            .import_csv(
+                Some(ITEMS.as_bytes()),
+                Some(LINKS.as_bytes()),
+                &columns,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(
+            "2 row(s) can't be imported, nothing was imported",
+            err.to_string()
+        );
+        let Error::ImportFailed(report) = err else {
+            panic!("unexpected error {err}");
+        };
+        let errors = report
+            .errors()
+            .map(|line| (line.line, line.result.as_ref().unwrap_err().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (
+                    3,
+                    "invalid value \"x\" for the column \"quantity\"".to_string()
+                ),
+                (4, "item 4 not found".to_string()),
+            ],
+            errors
+        );
+        assert!(store.get_items().unwrap().is_empty());
+    }
+
+    #[test]
+    fn dry_run() {
+        let mut store = Store::open(":memory:").unwrap();
+        store.new_item("3", "room").unwrap();
+        store.new_item("1", "chair").unwrap();
+        store.add_child("3", "1", 10.0).unwrap();
+        let links = "parent,child,qty\n3,1,25\n";
+        let columns = ImportColumns {
+            parent_pn: "parent".to_string(),
+            child_pn: "child".to_string(),
+            quantity: "qty".to_string(),
+            ..Default::default()
+        };
+        let report = store
+            .import_csv(None::<&[u8]>, Some(links.as_bytes()), &columns, true)
+            .unwrap();
+        assert!(report.dry_run);
+        assert_eq!(
+            vec!["links 2 : change the quantity of 1 in 3 from 10 to 25"],
+            changes(&report)
+        );
+        assert_eq!(10.0, store.get_children("3").unwrap()[0].1.value);
+
+        let err = store
+            .import_csv(Some(links.as_bytes()), None::<&[u8]>, &columns, true)
+            .unwrap_err();
+        assert_eq!("the column \"pn\" is missing", err.to_string());
+    }
+}
@@ -1,12 +1,248 @@
+mod attribute;
 mod database;
+mod export;
+mod import;
+mod migrations;
+mod pn;
+mod search;
 mod store;
+mod tree;
+mod unit;
 
-pub use database::{Database, Item};
+pub use attribute::{AttributeType, AttributeValue, Attributes};
+pub use database::{Database, Item, ItemMaturity};
+pub use export::GraphFormat;
+pub use import::{CsvTable, ImportChange, ImportColumns, ImportLine, ImportReport};
+pub use migrations::SCHEMA_VERSION;
+pub use pn::{
+    pn_provider, DatePnProvider, LuhnPnProvider, PnProvider, PrefixedPnProvider, SimplePnProvider,
+};
+pub use search::SearchQuery;
 pub use store::Store;
+pub use tree::{ItemQuantity, TreeNode, UsagePath};
+pub use unit::{format_value, parse_link_quantity, Quantity, Unit};
 
 #[derive(Debug)]
 pub enum Error {
     DatabaseErr(rusqlite::Error),
+    /// The store was created by a newer version of pbs, with a schema
+    /// version greater than the supported one
+    UnsupportedSchema {
+        version: usize,
+        supported: usize,
+    },
+    /// No item has the given PN
+    ItemNotFound(String),
+    /// The item has no such revision
+    RevisionNotFound {
+        pn: String,
+        version: usize,
+    },
+    /// An item with the same PN already exists
+    DuplicatePn(String),
+    /// The item is already a child of the parent item
+    ChildAlreadyLinked {
+        pn: String,
+        child_pn: String,
+    },
+    /// The product structure would contain a cycle, given as the list of
+    /// PNs of the path, starting and ending with the same PN
+    CycleDetected(Vec<String>),
+    /// The item can't go from a maturity to another
+    InvalidTransition {
+        pn: String,
+        from: ItemMaturity,
+        to: ItemMaturity,
+    },
+    /// The item can't be released because one of its children is not
+    UnreleasedChild {
+        pn: String,
+        child_pn: String,
+    },
+    /// The operation requires the item to be released
+    NotReleased {
+        pn: String,
+        version: usize,
+    },
+    /// The item is released or obsolete, as given by its maturity, so it
+    /// can't be modified : a new revision must be created instead
+    FrozenItem {
+        pn: String,
+        version: usize,
+        maturity: ItemMaturity,
+    },
+    /// The item is not a child of the parent item
+    ChildNotLinked {
+        pn: String,
+        child_pn: String,
+    },
+    /// The item can't be deleted because it is used in the given parents
+    ItemUsed {
+        pn: String,
+        parents: Vec<String>,
+    },
+    /// A file can't be opened, read or written
+    FileErr {
+        path: String,
+        error: std::io::Error,
+    },
+    /// A CSV file can't be read or written, or is malformed
+    CsvErr(csv::Error),
+    /// An .xlsx workbook can't be written
+    XlsxErr(rust_xlsxwriter::XlsxError),
+    /// A column is missing from the header of a CSV file
+    MissingColumn(String),
+    /// The value of a column can't be used, e.g. an empty PN or a quantity
+    /// which is not a number
+    InvalidValue {
+        column: String,
+        value: String,
+    },
+    /// No PN scheme has the given name
+    UnknownPnScheme(String),
+    /// The PN doesn't follow the PN scheme of the store
+    InvalidPn {
+        pn: String,
+        scheme: String,
+    },
+    /// The category is not used by the PN scheme, or is required and
+    /// missing (empty)
+    InvalidCategory {
+        category: String,
+        scheme: String,
+    },
+    /// The text of an attribute value doesn't match its type
+    InvalidAttributeValue {
+        kind: AttributeType,
+        value: String,
+    },
+    /// The item has no attribute with the given key
+    AttributeNotFound {
+        pn: String,
+        key: String,
+    },
+    /// No unit has the given symbol
+    UnknownUnit(String),
+    /// A quantity can't be converted between units of different dimensions,
+    /// e.g. from metres to kilograms
+    IncompatibleUnits {
+        from: Unit,
+        to: Unit,
+    },
+    /// The quantity of a link is not a positive number
+    InvalidQuantity(String),
+    /// Some rows of a CSV import failed, so nothing was imported : their
+    /// errors are given in the report
+    ImportFailed(ImportReport),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DatabaseErr(e) => write!(f, "database error : {e}"),
+            Error::UnsupportedSchema { version, supported } => write!(
+                f,
+                "the store uses the schema version {version}, but only versions up to \
+                {supported} are supported : please upgrade pbs"
+            ),
+            Error::ItemNotFound(pn) => write!(f, "item {pn} not found"),
+            Error::RevisionNotFound { pn, version } => {
+                write!(f, "revision {pn}-{version:03} not found")
+            }
+            Error::DuplicatePn(pn) => write!(f, "an item with the PN {pn} already exists"),
+            Error::ChildAlreadyLinked { pn, child_pn } => write!(
+                f,
+                "{child_pn} is already a child of {pn}, change the quantity of the link instead"
+            ),
+            Error::CycleDetected(path) => {
+                write!(
+                    f,
+                    "the structure would contain a cycle : {}",
+                    path.join(" -> ")
+                )
+            }
+            Error::InvalidTransition { pn, from, to } => {
+                write!(f, "{pn} can't go from \"{from}\" to \"{to}\"")
+            }
+            Error::UnreleasedChild { pn, child_pn } => write!(
+                f,
+                "{pn} can't be released because its child {child_pn} is not released"
+            ),
+            Error::NotReleased { pn, version } => {
+                write!(f, "{pn}-{version:03} is not released")
+            }
+            Error::FrozenItem {
+                pn,
+                version,
+                maturity,
+            } => write!(
+                f,
+                "{pn}-{version:03} is {maturity} and can't be modified, create a new revision instead",
+                maturity = maturity.to_string().to_lowercase()
+            ),
+            Error::ChildNotLinked { pn, child_pn } => {
+                write!(f, "{child_pn} is not a child of {pn}")
+            }
+            Error::ItemUsed { pn, parents } => write!(
+                f,
+                "{pn} is used in {}, remove it from its parents first or force the deletion",
+                parents.join(", ")
+            ),
+            Error::FileErr { path, error } => write!(f, "{path} : {error}"),
+            Error::CsvErr(e) => write!(f, "CSV error : {e}"),
+            Error::XlsxErr(e) => write!(f, "xlsx error : {e}"),
+            Error::MissingColumn(column) => write!(f, "the column \"{column}\" is missing"),
+            Error::InvalidValue { column, value } => {
+                write!(f, "invalid value \"{value}\" for the column \"{column}\"")
+            }
+            Error::UnknownPnScheme(scheme) => write!(f, "unknown PN scheme \"{scheme}\""),
+            Error::InvalidPn { pn, scheme } => {
+                write!(f, "{pn} doesn't follow the PN scheme {scheme} of the store")
+            }
+            Error::InvalidCategory { category, scheme } if category.is_empty() => {
+                write!(f, "the PN scheme {scheme} requires a category")
+            }
+            Error::InvalidCategory { category, scheme } => {
+                write!(
+                    f,
+                    "invalid category \"{category}\" for the PN scheme {scheme}"
+                )
+            }
+            Error::InvalidAttributeValue { kind, value } => {
+                write!(f, "invalid {kind} \"{value}\"")
+            }
+            Error::AttributeNotFound { pn, key } => {
+                write!(f, "{pn} has no attribute \"{key}\"")
+            }
+            Error::UnknownUnit(unit) => write!(f, "unknown unit \"{unit}\""),
+            Error::IncompatibleUnits { from, to } => {
+                write!(f, "{from} can't be converted to {to}")
+            }
+            Error::InvalidQuantity(quantity) => {
+                write!(
+                    f,
+                    "invalid quantity \"{quantity}\", it must be a positive number"
+                )
+            }
+            Error::ImportFailed(report) => write!(
+                f,
+                "{} row(s) can't be imported, nothing was imported",
+                report.errors().count()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::DatabaseErr(e) => Some(e),
+            Error::FileErr { error, .. } => Some(error),
+            Error::CsvErr(e) => Some(e),
+            Error::XlsxErr(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
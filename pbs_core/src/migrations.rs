@@ -0,0 +1,109 @@
+use rusqlite::Connection;
+
+use crate::{Error, Result};
+
+/// Migrations of the database schema, in order
+///
+/// The version of a database schema is the number of migrations applied to
+/// it, saved in `PRAGMA user_version`. Stores created before the schema was
+/// versioned have the version 0, and the first migration must be compatible
+/// with them. A migration must never be changed once released : any schema
+/// change requires a new migration.
+const MIGRATIONS: &[&str] = &[
+    include_str!("migrations/001_init.sql"),
+    include_str!("migrations/002_item_revisions.sql"),
+    include_str!("migrations/003_item_attributes.sql"),
+    include_str!("migrations/004_units.sql"),
+];
+
+/// Version of the schema supported by this version of pbs
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Get the version of the database schema
+pub(crate) fn schema_version(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Upgrade the database schema to [SCHEMA_VERSION]
+///
+/// Each migration is run in its own transaction. Databases with a newer
+/// schema are refused.
+pub(crate) fn migrate(conn: &mut Connection) -> Result<()> {
+    let version = schema_version(conn).map_err(Error::DatabaseErr)?;
+    if version > SCHEMA_VERSION {
+        return Err(Error::UnsupportedSchema {
+            version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    // tables referenced by foreign keys may be rebuilt by the migrations
+    conn.pragma_update(None, "foreign_keys", false)
+        .map_err(Error::DatabaseErr)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().map_err(Error::DatabaseErr)?;
+        tx.execute_batch(migration).map_err(Error::DatabaseErr)?;
+        tx.pragma_update(None, "user_version", index + 1)
+            .map_err(Error::DatabaseErr)?;
+        tx.commit().map_err(Error::DatabaseErr)?;
+    }
+    conn.pragma_update(None, "foreign_keys", true)
+        .map_err(Error::DatabaseErr)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn new_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(SCHEMA_VERSION, schema_version(&conn).unwrap());
+        // migrating an up to date database does nothing
+        migrate(&mut conn).unwrap();
+        assert_eq!(SCHEMA_VERSION, schema_version(&conn).unwrap());
+    }
+
+    #[test]
+    fn unversioned_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("migrations/001_init.sql"))
+            .unwrap();
+        conn.execute_batch(
+            "INSERT INTO items(id, pn, name, maturity, version) VALUES(1, 'A', 'PARENT', 1, 1);
+            INSERT INTO items(id, pn, name, maturity, version) VALUES(2, 'B', 'CHILD', 1, 1);
+            INSERT INTO children(id_parent, id_child, quantity) VALUES(1, 2, 3);",
+        )
+        .unwrap();
+        assert_eq!(0, schema_version(&conn).unwrap());
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(SCHEMA_VERSION, schema_version(&conn).unwrap());
+        let (quantity, unit): (f64, String) = conn
+            .query_row(
+                "SELECT quantity, link_unit FROM view_children WHERE pn = 'B'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((3.0, "each".to_string()), (quantity, unit));
+        // a new revision can be added
+        conn.execute(
+            "INSERT INTO items(pn, name, maturity, version) VALUES('A', 'PARENT', 0, 2)",
+            (),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        assert!(matches!(
+            migrate(&mut conn),
+            Err(Error::UnsupportedSchema { version, supported })
+                if version == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION
+        ));
+    }
+}
@@ -1,5 +1,3 @@
-PRAGMA foreign_keys = ON;
-
 CREATE TABLE IF NOT EXISTS items(
     id        INTEGER PRIMARY KEY,
     pn        TEXT,
@@ -0,0 +1,43 @@
+-- Items are unique by PN and version, to keep all the revisions of an item
+
+DROP VIEW IF EXISTS view_children;
+DROP VIEW IF EXISTS view_where_used;
+
+CREATE TABLE items_revisions(
+    id        INTEGER PRIMARY KEY,
+    pn        TEXT,
+    name      TEXT,
+    maturity  INTEGER,
+    version   INTEGER,
+    UNIQUE(pn, version)
+);
+
+INSERT INTO items_revisions(id, pn, name, maturity, version)
+    SELECT id, pn, name, maturity, version FROM items;
+
+DROP TABLE items;
+
+ALTER TABLE items_revisions RENAME TO items;
+
+CREATE VIEW view_children AS
+    SELECT
+        items.id, 
+        items.pn, 
+        items.name, 
+        items.version,
+        items.maturity,
+        children.quantity,
+        children.id_parent
+    FROM items, children 
+    WHERE children.id_child = items.id;
+    
+CREATE VIEW view_where_used AS
+    SELECT
+        children.id_parent as id,
+        items.pn, 
+        items.name,
+        items.version,
+        items.maturity,
+        children.id_child
+    FROM items, children 
+    WHERE children.id_parent = items.id;
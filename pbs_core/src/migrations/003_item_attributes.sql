@@ -0,0 +1,10 @@
+-- Custom attributes of the items, the values being saved as text
+
+CREATE TABLE attributes(
+    id_item  INTEGER,
+    key      TEXT,
+    type     TEXT,
+    value    TEXT,
+    FOREIGN KEY(id_item) REFERENCES items(id),
+    UNIQUE(id_item, key)
+);
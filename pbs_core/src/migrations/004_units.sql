@@ -0,0 +1,50 @@
+-- Units of measure : each item has a unit, and the quantity of a link is a
+-- real number in a unit compatible with the unit of the child
+
+ALTER TABLE items ADD COLUMN unit TEXT NOT NULL DEFAULT 'each';
+
+DROP VIEW IF EXISTS view_children;
+DROP VIEW IF EXISTS view_where_used;
+
+CREATE TABLE children_units(
+    id_parent  INTEGER,
+    id_child   INTEGER,
+    quantity   REAL,
+    unit       TEXT NOT NULL DEFAULT 'each',
+    FOREIGN KEY(id_parent) REFERENCES items(id),
+    FOREIGN KEY(id_child) REFERENCES items(id),
+    UNIQUE(id_parent, id_child)
+);
+
+INSERT INTO children_units(id_parent, id_child, quantity)
+    SELECT id_parent, id_child, quantity FROM children;
+
+DROP TABLE children;
+
+ALTER TABLE children_units RENAME TO children;
+
+CREATE VIEW view_children AS
+    SELECT
+        items.id,
+        items.pn,
+        items.name,
+        items.version,
+        items.maturity,
+        items.unit,
+        children.quantity,
+        children.unit AS link_unit,
+        children.id_parent
+    FROM items, children
+    WHERE children.id_child = items.id;
+
+CREATE VIEW view_where_used AS
+    SELECT
+        children.id_parent as id,
+        items.pn,
+        items.name,
+        items.version,
+        items.maturity,
+        items.unit,
+        children.id_child
+    FROM items, children
+    WHERE children.id_parent = items.id;
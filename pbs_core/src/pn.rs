@@ -0,0 +1,260 @@
+//! Allocation of the PNs of the items created without PN, following the
+//! scheme configured in the store
+
+use crate::{Database, Error, Result};
+
+/// Config key of the PN scheme of a store
+pub(crate) const PN_SCHEME_KEY: &str = "pn_scheme";
+
+/// A PN allocation scheme
+///
+/// The state of the scheme, e.g. its counters, is saved in the config of
+/// the store.
+pub trait PnProvider {
+    /// Name of the scheme, as saved in the config of the store
+    fn name(&self) -> &'static str;
+
+    /// Allocate a new PN, in the given category for the schemes using
+    /// categories
+    fn next_pn(&self, db: &mut Database, category: Option<&str>) -> Result<String>;
+
+    /// Check that a PN given manually follows the scheme
+    fn is_valid(&self, pn: &str) -> bool;
+}
+
+/// Get the built-in provider of the scheme with the given name
+pub fn pn_provider(name: &str) -> Result<Box<dyn PnProvider>> {
+    match name {
+        "simple" => Ok(Box::new(SimplePnProvider)),
+        "prefixed" => Ok(Box::new(PrefixedPnProvider)),
+        "date" => Ok(Box::new(DatePnProvider)),
+        "luhn" => Ok(Box::new(LuhnPnProvider)),
+        _ => Err(Error::UnknownPnScheme(name.to_string())),
+    }
+}
+
+/// Increment the counter saved under `key` in the config, and return it
+fn next_counter(db: &mut Database, key: &str) -> Result<usize> {
+    let counter = db.get_config(key)?.parse::<usize>().unwrap_or(0) + 1;
+    db.set_config(key, &counter.to_string())?;
+    Ok(counter)
+}
+
+/// Check that `s` is made of `len` digits
+fn is_digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Fail if a category is given to a scheme which doesn't use categories
+fn check_no_category(scheme: &dyn PnProvider, category: Option<&str>) -> Result<()> {
+    match category {
+        Some(category) => Err(Error::InvalidCategory {
+            category: category.to_string(),
+            scheme: scheme.name().to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// 8 digits sequence : `00000001`, `00000002`...
+///
+/// It is the default scheme, used when none is configured.
+pub struct SimplePnProvider;
+
+impl PnProvider for SimplePnProvider {
+    fn name(&self) -> &'static str {
+        "simple"
+    }
+
+    fn next_pn(&self, db: &mut Database, category: Option<&str>) -> Result<String> {
+        check_no_category(self, category)?;
+        Ok(format!("{:08}", next_counter(db, "simple_pn_provider")?))
+    }
+
+    fn is_valid(&self, pn: &str) -> bool {
+        is_digits(pn, 8)
+    }
+}
+
+/// A 6 digits sequence per category, prefixed with the category, e.g.
+/// `ASM-000123` and `PRT-000456`
+///
+/// Categories are made of uppercase letters and digits.
+pub struct PrefixedPnProvider;
+
+impl PrefixedPnProvider {
+    fn is_category(category: &str) -> bool {
+        !category.is_empty()
+            && category
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    }
+}
+
+impl PnProvider for PrefixedPnProvider {
+    fn name(&self) -> &'static str {
+        "prefixed"
+    }
+
+    fn next_pn(&self, db: &mut Database, category: Option<&str>) -> Result<String> {
+        let category = category.unwrap_or_default();
+        if !Self::is_category(category) {
+            return Err(Error::InvalidCategory {
+                category: category.to_string(),
+                scheme: self.name().to_string(),
+            });
+        }
+        let counter = next_counter(db, &format!("pn_counter.{category}"))?;
+        Ok(format!("{category}-{counter:06}"))
+    }
+
+    fn is_valid(&self, pn: &str) -> bool {
+        pn.split_once('-')
+            .is_some_and(|(category, number)| Self::is_category(category) && is_digits(number, 6))
+    }
+}
+
+/// The creation date followed by a 4 digits sequence of the day, e.g.
+/// `20240131-0001`
+pub struct DatePnProvider;
+
+impl PnProvider for DatePnProvider {
+    fn name(&self) -> &'static str {
+        "date"
+    }
+
+    fn next_pn(&self, db: &mut Database, category: Option<&str>) -> Result<String> {
+        check_no_category(self, category)?;
+        let today = db.today()?;
+        let counter = next_counter(db, &format!("pn_counter.{today}"))?;
+        Ok(format!("{today}-{counter:04}"))
+    }
+
+    fn is_valid(&self, pn: &str) -> bool {
+        pn.split_once('-')
+            .is_some_and(|(date, number)| is_digits(date, 8) && is_digits(number, 4))
+    }
+}
+
+/// A 7 digits sequence followed by its Luhn (mod 10) check digit, e.g.
+/// `00000018`, which detects most typing errors
+pub struct LuhnPnProvider;
+
+impl LuhnPnProvider {
+    /// Compute the Luhn check digit of a number
+    fn check_digit(number: &str) -> Option<u32> {
+        let mut sum = 0;
+        for (i, c) in number.chars().rev().enumerate() {
+            let digit = c.to_digit(10)?;
+            sum += match (i % 2 == 0, digit * 2) {
+                (true, double) if double > 9 => double - 9,
+                (true, double) => double,
+                (false, _) => digit,
+            };
+        }
+        Some((10 - sum % 10) % 10)
+    }
+}
+
+impl PnProvider for LuhnPnProvider {
+    fn name(&self) -> &'static str {
+        "luhn"
+    }
+
+    fn next_pn(&self, db: &mut Database, category: Option<&str>) -> Result<String> {
+        check_no_category(self, category)?;
+        let number = format!("{:07}", next_counter(db, "pn_counter.luhn")?);
+        let digit = Self::check_digit(&number).expect("a formatted number has only digits");
+        Ok(format!("{number}{digit}"))
+    }
+
+    fn is_valid(&self, pn: &str) -> bool {
+        match pn.char_indices().last() {
+            Some((last, digit)) if last > 0 => Self::check_digit(&pn[..last])
+                .is_some_and(|check| digit.to_digit(10) == Some(check)),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let mut db = Database::open(":memory:").unwrap();
+        let provider = SimplePnProvider;
+        assert_eq!("00000001", provider.next_pn(&mut db, None).unwrap());
+        assert_eq!("00000002", provider.next_pn(&mut db, None).unwrap());
+        assert!(provider.next_pn(&mut db, Some("ASM")).is_err());
+        assert!(provider.is_valid("12345678"));
+        assert!(!provider.is_valid("1234567"));
+        assert!(!provider.is_valid("L289651"));
+    }
+
+    #[test]
+    fn prefixed() {
+        let mut db = Database::open(":memory:").unwrap();
+        let provider = PrefixedPnProvider;
+        assert_eq!(
+            "ASM-000001",
+            provider.next_pn(&mut db, Some("ASM")).unwrap()
+        );
+        assert_eq!(
+            "PRT-000001",
+            provider.next_pn(&mut db, Some("PRT")).unwrap()
+        );
+        assert_eq!(
+            "ASM-000002",
+            provider.next_pn(&mut db, Some("ASM")).unwrap()
+        );
+        let err = provider.next_pn(&mut db, None).unwrap_err();
+        assert_eq!(
+            "the PN scheme prefixed requires a category",
+            err.to_string()
+        );
+        assert!(provider.next_pn(&mut db, Some("asm")).is_err());
+        assert!(provider.is_valid("PRT-000456"));
+        assert!(!provider.is_valid("PRT-00456"));
+        assert!(!provider.is_valid("prt-000456"));
+        assert!(!provider.is_valid("000456"));
+    }
+
+    #[test]
+    fn date() {
+        let mut db = Database::open(":memory:").unwrap();
+        let provider = DatePnProvider;
+        let today = db.today().unwrap();
+        assert_eq!(
+            format!("{today}-0001"),
+            provider.next_pn(&mut db, None).unwrap()
+        );
+        assert_eq!(
+            format!("{today}-0002"),
+            provider.next_pn(&mut db, None).unwrap()
+        );
+        assert!(provider.is_valid("20240131-0012"));
+        assert!(!provider.is_valid("2024-01-31-0012"));
+    }
+
+    #[test]
+    fn luhn() {
+        let mut db = Database::open(":memory:").unwrap();
+        let provider = LuhnPnProvider;
+        assert_eq!("00000018", provider.next_pn(&mut db, None).unwrap());
+        assert_eq!("00000026", provider.next_pn(&mut db, None).unwrap());
+        assert!(provider.is_valid("79927398713"));
+        assert!(!provider.is_valid("79927398710"));
+        assert!(!provider.is_valid("00000081"));
+        assert!(!provider.is_valid("7"));
+        assert!(!provider.is_valid("ABC"));
+    }
+
+    #[test]
+    fn unknown_scheme() {
+        assert_eq!("luhn", pn_provider("luhn").unwrap().name());
+        let err = pn_provider("mine").err().unwrap();
+        assert_eq!("unknown PN scheme \"mine\"", err.to_string());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::ItemMaturity;
+
+/// Criteria to search items, see [crate::Store::search]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Text to find in the PN or the name of the items, ignoring the case
+    ///
+    /// It is a glob pattern matching the whole PN or name if it contains
+    /// `*`, `?` or `[`, e.g. `305.*`, and a substring otherwise. An empty
+    /// pattern matches all the items.
+    pub pattern: String,
+    /// Only the items with this maturity
+    pub maturity: Option<ItemMaturity>,
+    /// Only this version of the items, instead of their last revision
+    pub version: Option<usize>,
+    /// Only the items having these attribute values, given as key and value
+    ///
+    /// Number attributes are compared as numbers, and the other ones as
+    /// text ignoring the case, the value being a glob pattern if it
+    /// contains `*`, `?` or `[`.
+    pub attributes: Vec<(String, String)>,
+}
+
+impl SearchQuery {
+    /// Search the items matching `pattern`, with any maturity
+    pub fn new(pattern: &str) -> Self {
+        SearchQuery {
+            pattern: pattern.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn is_glob(&self) -> bool {
+        is_glob(&self.pattern)
+    }
+
+    /// The pattern for a SQL `GLOB` or `LIKE`, in lower case, the `LIKE`
+    /// special characters being escaped with `\`
+    pub(crate) fn sql_pattern(&self) -> String {
+        let pattern = self.pattern.to_ascii_lowercase();
+        if self.is_glob() {
+            return pattern;
+        }
+        let escaped = pattern
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        format!("%{escaped}%")
+    }
+}
+
+/// Check if a text is a glob pattern
+pub(crate) fn is_glob(text: &str) -> bool {
+    text.contains(['*', '?', '['])
+}
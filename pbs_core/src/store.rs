@@ -1,13 +1,42 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{database::Database, Item, Result};
+use crate::{
+    database::Database,
+    pn::{pn_provider, PN_SCHEME_KEY},
+    unit::check_quantity,
+    AttributeValue, Attributes, Error, Item, ItemMaturity, PnProvider, Quantity, Result,
+    SearchQuery, SimplePnProvider, TreeNode, Unit, UsagePath,
+};
 
-pub fn simple_8digits_pn_provider(db: &mut Database) -> Result<String> {
-    const KEY: &str = "simple_pn_provider";
-    let last_pn = db.get_config(KEY)?.parse::<usize>().unwrap_or(0);
-    let new_pn = format!("{:08}", last_pn + 1);
-    db.set_config(KEY, &new_pn)?;
-    Ok(new_pn)
+/// Build an [Error::CycleDetected] from a path of items
+fn cycle_error<'a>(path: impl Iterator<Item = &'a Item>) -> Error {
+    Error::CycleDetected(path.map(|item| item.pn().to_string()).collect())
+}
+
+/// Check that the item can be modified, i.e. it is not released nor obsolete
+fn check_editable(item: &Item) -> Result<()> {
+    if item.maturity().is_frozen() {
+        return Err(Error::FrozenItem {
+            pn: item.pn().to_string(),
+            version: item.version(),
+            maturity: item.maturity(),
+        });
+    }
+    Ok(())
+}
+
+/// The quantity of a link to the child, in the given unit or the unit of
+/// the child
+fn link_quantity(child_item: &Item, value: f64, unit: Option<Unit>) -> Result<Quantity> {
+    check_quantity(value)?;
+    let quantity = Quantity::new(value, unit.unwrap_or(child_item.unit()));
+    if !quantity.unit.is_compatible(child_item.unit()) {
+        return Err(Error::IncompatibleUnits {
+            from: quantity.unit,
+            to: child_item.unit(),
+        });
+    }
+    Ok(quantity)
 }
 
 pub struct Store {
@@ -15,12 +44,64 @@ pub struct Store {
 }
 
 impl Store {
-    /// Open the store
+    /// Open the store, creating an empty one if it doesn't exist
     pub fn open(url: &str) -> Result<Self> {
         let db = Database::open(url)?;
         Ok(Store { db })
     }
 
+    /// Open an existing store, failing if it doesn't exist, e.g. when it
+    /// was deleted
+    pub fn open_existing(url: &str) -> Result<Self> {
+        let db = Database::open_existing(url)?;
+        Ok(Store { db })
+    }
+
+    /// Save a copy of the store to a new file, e.g. to start a new store
+    /// from an existing one
+    ///
+    /// The file must not exist, and the copy is not opened.
+    pub fn save_as(&self, path: &str) -> Result<()> {
+        self.db.save_as(path)
+    }
+
+    /// Run `f` in a transaction : the changes made by `f` are all saved if it
+    /// succeeds, and all cancelled if it fails
+    ///
+    /// Transactions can be nested, a failing inner transaction only cancelling
+    /// its own changes.
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&mut Store) -> Result<T>) -> Result<T> {
+        self.begin_transaction()?;
+        match f(self) {
+            Ok(value) => {
+                self.commit_transaction()?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.rollback_transaction()?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Start a transaction, which must be ended by
+    /// [Store::commit_transaction] or [Store::rollback_transaction]
+    ///
+    /// Prefer [Store::transaction] when the changes can be made in a closure.
+    pub fn begin_transaction(&mut self) -> Result<()> {
+        self.db.begin()
+    }
+
+    /// Save the changes made since the last [Store::begin_transaction]
+    pub fn commit_transaction(&mut self) -> Result<()> {
+        self.db.commit()
+    }
+
+    /// Cancel the changes made since the last [Store::begin_transaction]
+    pub fn rollback_transaction(&mut self) -> Result<()> {
+        self.db.rollback()
+    }
+
     /// Get a config value from the database
     pub fn get_config(&self, key: &str) -> Result<String> {
         self.db.get_config(key)
@@ -33,51 +114,1212 @@ impl Store {
 
     /// Create a new item, allocating a new PN
     pub fn create(&mut self, name: &str) -> Result<Item> {
-        let pn = simple_8digits_pn_provider(&mut self.db)?;
-        self.db.insert_item(&pn, name)
+        self.create_in(None, name)
+    }
+
+    /// Create an item with a new PN, allocated in the given `category` by
+    /// the PN scheme of the store
+    ///
+    /// PNs already used, e.g. by items added manually, are skipped.
+    pub fn create_in(&mut self, category: Option<&str>, name: &str) -> Result<Item> {
+        let provider = self
+            .pn_provider()?
+            .unwrap_or_else(|| Box::new(SimplePnProvider));
+        self.transaction(|store| loop {
+            let pn = provider.next_pn(&mut store.db, category)?;
+            match store.db.get_item_by_pn(&pn) {
+                Err(Error::ItemNotFound(_)) => return store.db.insert_item(&pn, name),
+                Err(e) => return Err(e),
+                Ok(_) => continue,
+            }
+        })
+    }
+
+    /// Get the PN scheme configured in the store, if any
+    pub fn pn_provider(&self) -> Result<Option<Box<dyn PnProvider>>> {
+        match self.db.get_config(PN_SCHEME_KEY)?.as_str() {
+            "" => Ok(None),
+            name => pn_provider(name).map(Some),
+        }
+    }
+
+    /// Set the PN scheme of the store, by name (see [pn_provider])
+    ///
+    /// Once a scheme is set, the PNs of the items added with
+    /// [Store::new_item] must follow it.
+    pub fn set_pn_scheme(&mut self, name: &str) -> Result<()> {
+        pn_provider(name)?;
+        self.db.set_config(PN_SCHEME_KEY, name)
     }
 
     // Add a new item to the store
     pub fn new_item(&mut self, pn: &str, name: &str) -> Result<Item> {
+        if let Some(provider) = self.pn_provider()? {
+            if !provider.is_valid(pn) {
+                return Err(Error::InvalidPn {
+                    pn: pn.to_string(),
+                    scheme: provider.name().to_string(),
+                });
+            }
+        }
         self.db.insert_item(pn, name)
     }
 
+    /// Create a new revision of a released item
+    ///
+    /// The new revision is in progress, and uses the same children as the
+    /// released one, which is kept unchanged.
+    pub fn revise(&mut self, pn: &str) -> Result<Item> {
+        let item = self.db.get_item_by_pn(pn)?;
+        if item.maturity() != ItemMaturity::Released {
+            return Err(Error::NotReleased {
+                pn: pn.to_string(),
+                version: item.version(),
+            });
+        }
+        self.db.insert_revision(&item)
+    }
+
+    /// Get all the revisions of an item, from the oldest to the last one
+    pub fn get_revisions(&self, pn: &str) -> Result<Vec<Item>> {
+        self.db.get_revisions(pn)
+    }
+
+    /// Get the last revision of an item
+    pub fn get_item_by_pn(&self, pn: &str) -> Result<Item> {
+        self.db.get_item_by_pn(pn)
+    }
+
+    /// Get a given revision of an item
+    pub fn get_item(&self, pn: &str, version: usize) -> Result<Item> {
+        self.db.get_item(pn, version)
+    }
+
     /// Save the item
+    ///
+    /// A released or obsolete item can't be modified.
     pub fn save_item(&mut self, item: Item) -> Result<()> {
+        check_editable(&self.db.reload_item(&item)?)?;
         self.db.update_item(item)
     }
 
-    /// Get all items
+    /// Change the maturity of an item
+    ///
+    /// Only the transitions allowed by [ItemMaturity::can_become] are
+    /// accepted, and an item can only be released when all its children
+    /// are released.
+    pub fn set_maturity(&mut self, pn: &str, maturity: ItemMaturity) -> Result<Item> {
+        let item = self.db.get_item_by_pn(pn)?;
+        if !item.maturity().can_become(maturity) {
+            return Err(Error::InvalidTransition {
+                pn: pn.to_string(),
+                from: item.maturity(),
+                to: maturity,
+            });
+        }
+        if maturity == ItemMaturity::Released {
+            let children = self.db.get_children(&item)?;
+            if let Some((child, _)) = children
+                .iter()
+                .find(|(child, _)| child.maturity() != ItemMaturity::Released)
+            {
+                return Err(Error::UnreleasedChild {
+                    pn: pn.to_string(),
+                    child_pn: child.pn().to_string(),
+                });
+            }
+        }
+        self.db.update_maturity(&item, maturity)?;
+        self.db.get_item_by_pn(pn)
+    }
+
+    /// Get the attributes of an item
+    pub fn get_attributes(&self, item: &Item) -> Result<Attributes> {
+        self.db.get_attributes(item)
+    }
+
+    /// Set an attribute of the last revision of an item, replacing its
+    /// previous value even if it had another type
+    ///
+    /// A released or obsolete item can't be modified.
+    pub fn set_attribute(&mut self, pn: &str, key: &str, value: &AttributeValue) -> Result<()> {
+        let item = self.db.get_item_by_pn(pn)?;
+        check_editable(&item)?;
+        self.db.set_attribute(&item, key, value)
+    }
+
+    /// Remove an attribute from the last revision of an item
+    pub fn remove_attribute(&mut self, pn: &str, key: &str) -> Result<()> {
+        let item = self.db.get_item_by_pn(pn)?;
+        check_editable(&item)?;
+        self.db.delete_attribute(&item, key)
+    }
+
+    /// Change the unit of measure of an item
+    ///
+    /// The quantities of the item in its parents must be convertible to the
+    /// new unit, e.g. an item used by the metre can go to millimetres but
+    /// not to kilograms.
+    pub fn set_unit(&mut self, pn: &str, unit: Unit) -> Result<()> {
+        let item = self.db.get_item_by_pn(pn)?;
+        check_editable(&item)?;
+        for (_, quantity) in self.db.get_parents(&item)? {
+            if !quantity.unit.is_compatible(unit) {
+                return Err(Error::IncompatibleUnits {
+                    from: quantity.unit,
+                    to: unit,
+                });
+            }
+        }
+        self.db.update_unit(&item, unit)
+    }
+
+    /// Get the last revision of all items
     pub fn get_items(&self) -> Result<Vec<Item>> {
         self.db.get_items()
     }
 
-    /// Add a child to an item
-    pub fn add_child(&mut self, parent_pn: &str, child_pn: &str, quantity: usize) -> Result<()> {
+    /// Search the items matching the `query`, ordered by PN
+    ///
+    /// Only the last revision of the items is searched, unless a version is
+    /// given.
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<Item>> {
+        self.db.search_items(query)
+    }
+
+    /// Add a child to an item, the quantity being in the unit of the child
+    ///
+    /// The link is refused with [Error::CycleDetected] if the parent is
+    /// the child itself, or is already used in the child structure, and
+    /// with [Error::FrozenItem] if the parent is released or obsolete.
+    pub fn add_child(&mut self, parent_pn: &str, child_pn: &str, quantity: f64) -> Result<()> {
+        self.add_link(parent_pn, child_pn, quantity, None)
+    }
+
+    /// Add a child to an item, the quantity being in another unit than the
+    /// unit of the child, e.g. 500 mm of a cable counted in metres
+    ///
+    /// The unit must be compatible with the unit of the child, see
+    /// [Store::add_child] for the other checks.
+    pub fn add_child_in(
+        &mut self,
+        parent_pn: &str,
+        child_pn: &str,
+        quantity: f64,
+        unit: Unit,
+    ) -> Result<()> {
+        self.add_link(parent_pn, child_pn, quantity, Some(unit))
+    }
+
+    fn add_link(
+        &mut self,
+        parent_pn: &str,
+        child_pn: &str,
+        quantity: f64,
+        unit: Option<Unit>,
+    ) -> Result<()> {
         let parent_item = self.db.get_item_by_pn(parent_pn)?;
         let child_item = self.db.get_item_by_pn(child_pn)?;
+        let quantity = link_quantity(&child_item, quantity, unit)?;
+        self.check_link(&parent_item, &child_item)?;
         self.db.add_child(&parent_item, &child_item, quantity)
     }
 
-    /// Get all items children
-    pub fn get_children(&self, pn: &str) -> Result<Vec<(Item, usize)>> {
+    /// Check that a child can be added to an item, without adding it
+    ///
+    /// It fails with the errors of [Store::add_child], e.g. to warn about a
+    /// cycle before the link is made.
+    pub fn check_add_child(&self, parent_pn: &str, child_pn: &str) -> Result<()> {
+        let parent_item = self.db.get_item_by_pn(parent_pn)?;
+        let child_item = self.db.get_item_by_pn(child_pn)?;
+        self.check_link(&parent_item, &child_item)?;
+        let children = self.db.get_children(&parent_item)?;
+        if children.iter().any(|(child, _)| *child == child_item) {
+            return Err(Error::ChildAlreadyLinked {
+                pn: parent_pn.to_string(),
+                child_pn: child_pn.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that a child can be moved from an item to another one, without
+    /// moving it
+    ///
+    /// It fails with the errors of [Store::move_child], e.g. to warn that
+    /// the current parent is released before the move is made.
+    pub fn check_move_child(
+        &self,
+        parent_pn: &str,
+        new_parent_pn: &str,
+        child_pn: &str,
+    ) -> Result<()> {
+        let (parent_item, _, _) = self.get_child_link(parent_pn, child_pn)?;
+        check_editable(&parent_item)?;
+        self.check_add_child(new_parent_pn, child_pn)
+    }
+
+    /// Move a child from an item to another one, with the same quantity
+    ///
+    /// Both items must be editable, the child must not already be linked to
+    /// the new parent, and the move is refused with [Error::CycleDetected]
+    /// if the new parent is used in the child structure.
+    pub fn move_child(
+        &mut self,
+        parent_pn: &str,
+        new_parent_pn: &str,
+        child_pn: &str,
+    ) -> Result<()> {
+        self.transaction(|store| {
+            store.check_move_child(parent_pn, new_parent_pn, child_pn)?;
+            let (parent_item, child_item, quantity) = store.get_child_link(parent_pn, child_pn)?;
+            let new_parent_item = store.db.get_item_by_pn(new_parent_pn)?;
+            store.db.remove_child(&parent_item, &child_item)?;
+            store.db.add_child(&new_parent_item, &child_item, quantity)
+        })
+    }
+
+    /// Check that the parent is editable, and that linking the child would
+    /// not make a cycle
+    fn check_link(&self, parent_item: &Item, child_item: &Item) -> Result<()> {
+        check_editable(parent_item)?;
+        if let Some(path) = self.find_path(child_item, parent_item)? {
+            let cycle = std::iter::once(parent_item).chain(path.iter());
+            return Err(cycle_error(cycle));
+        }
+        Ok(())
+    }
+
+    /// Remove a child from an item
+    pub fn remove_child(&mut self, parent_pn: &str, child_pn: &str) -> Result<()> {
+        let (parent_item, child_item, _) = self.get_child_link(parent_pn, child_pn)?;
+        check_editable(&parent_item)?;
+        self.db.remove_child(&parent_item, &child_item)
+    }
+
+    /// Change the quantity of a child in an item, keeping the unit of the
+    /// link
+    pub fn set_child_quantity(
+        &mut self,
+        parent_pn: &str,
+        child_pn: &str,
+        quantity: f64,
+    ) -> Result<()> {
+        let (parent_item, child_item, old_quantity) = self.get_child_link(parent_pn, child_pn)?;
+        check_editable(&parent_item)?;
+        let quantity = link_quantity(&child_item, quantity, Some(old_quantity.unit))?;
+        self.db
+            .update_child_quantity(&parent_item, &child_item, quantity)
+    }
+
+    /// Change the quantity of a child in an item, and the unit of the link
+    pub fn set_child_quantity_in(
+        &mut self,
+        parent_pn: &str,
+        child_pn: &str,
+        quantity: f64,
+        unit: Unit,
+    ) -> Result<()> {
+        let (parent_item, child_item, _) = self.get_child_link(parent_pn, child_pn)?;
+        check_editable(&parent_item)?;
+        let quantity = link_quantity(&child_item, quantity, Some(unit))?;
+        self.db
+            .update_child_quantity(&parent_item, &child_item, quantity)
+    }
+
+    /// Get the link between the last revision of an item and one of its
+    /// children, which may not be the last revision of the child
+    fn get_child_link(&self, parent_pn: &str, child_pn: &str) -> Result<(Item, Item, Quantity)> {
+        let parent_item = self.db.get_item_by_pn(parent_pn)?;
+        let (child_item, quantity) = self
+            .db
+            .get_children(&parent_item)?
+            .into_iter()
+            .find(|(child, _)| child.pn() == child_pn)
+            .ok_or_else(|| Error::ChildNotLinked {
+                pn: parent_pn.to_string(),
+                child_pn: child_pn.to_string(),
+            })?;
+        Ok((parent_item, child_item, quantity))
+    }
+
+    /// Delete the last revision of an item
+    ///
+    /// Deleting the only revision of an item removes the item from the store,
+    /// while deleting a newer revision cancels it. Released and obsolete
+    /// revisions can't be deleted.
+    ///
+    /// An item used in other items is only deleted if `force` is set, in
+    /// which case it is also removed from its parents.
+    pub fn delete_item(&mut self, pn: &str, force: bool) -> Result<()> {
+        let item = self.db.get_item_by_pn(pn)?;
+        check_editable(&item)?;
+        let parents = self.db.get_parents(&item)?;
+        if !parents.is_empty() {
+            if !force {
+                return Err(Error::ItemUsed {
+                    pn: pn.to_string(),
+                    parents: parents.iter().map(|(p, _)| p.pn().to_string()).collect(),
+                });
+            }
+            for (parent, _) in &parents {
+                check_editable(parent)?;
+            }
+        }
+        self.db.delete_item(&item)
+    }
+
+    /// Find a path going down the structure from `from` to `to`
+    fn find_path(&self, from: &Item, to: &Item) -> Result<Option<Vec<Item>>> {
+        let mut visited = HashSet::new();
+        let mut path = vec![from.clone()];
+        if self.find_path_rec(to, &mut path, &mut visited)? {
+            Ok(Some(path))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn find_path_rec(
+        &self,
+        to: &Item,
+        path: &mut Vec<Item>,
+        visited: &mut HashSet<Item>,
+    ) -> Result<bool> {
+        let current = path.last().expect("path is never empty").clone();
+        if &current == to {
+            return Ok(true);
+        }
+        if !visited.insert(current.clone()) {
+            return Ok(false);
+        }
+        for (child, _) in self.db.get_children(&current)? {
+            path.push(child);
+            if self.find_path_rec(to, path, visited)? {
+                return Ok(true);
+            }
+            path.pop();
+        }
+        Ok(false)
+    }
+
+    /// Scan the whole store for cycles in the product structure
+    ///
+    /// Each cycle is returned as the path of items, starting and ending with
+    /// the same item. An empty list means that the store is consistent.
+    pub fn find_cycles(&self) -> Result<Vec<Vec<Item>>> {
+        let mut cycles = Vec::new();
+        let mut done = HashSet::new();
+        for item in self.db.get_items()? {
+            let mut path = Vec::new();
+            self.find_cycles_rec(item, &mut path, &mut done, &mut cycles)?;
+        }
+        Ok(cycles)
+    }
+
+    fn find_cycles_rec(
+        &self,
+        item: Item,
+        path: &mut Vec<Item>,
+        done: &mut HashSet<Item>,
+        cycles: &mut Vec<Vec<Item>>,
+    ) -> Result<()> {
+        if let Some(pos) = path.iter().position(|i| i == &item) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(item);
+            cycles.push(cycle);
+            return Ok(());
+        }
+        if done.contains(&item) {
+            return Ok(());
+        }
+        let children = self.db.get_children(&item)?;
+        path.push(item);
+        for (child, _) in children {
+            self.find_cycles_rec(child, path, done, cycles)?;
+        }
+        let item = path.pop().expect("item pushed above");
+        done.insert(item);
+        Ok(())
+    }
+
+    /// Get all items children, with their quantity in the item
+    pub fn get_children(&self, pn: &str) -> Result<Vec<(Item, Quantity)>> {
         let item = self.db.get_item_by_pn(pn)?;
         self.db.get_children(&item)
     }
 
+    /// Get the multi-level structure of the given item
+    ///
+    /// `depth` limits the number of levels below the item, `None` meaning
+    /// that the whole structure is returned.
+    pub fn get_tree(&self, pn: &str, depth: Option<usize>) -> Result<TreeNode> {
+        let item = self.db.get_item_by_pn(pn)?;
+        let mut root = TreeNode::root(item);
+        self.build_tree(&mut root, depth, &mut Vec::new())?;
+        Ok(root)
+    }
+
+    fn build_tree(
+        &self,
+        node: &mut TreeNode,
+        depth: Option<usize>,
+        path: &mut Vec<Item>,
+    ) -> Result<()> {
+        if depth.is_some_and(|depth| node.level >= depth) {
+            return Ok(());
+        }
+        path.push(node.item.clone());
+        for (child, quantity) in self.db.get_children(&node.item)? {
+            if path.contains(&child) {
+                return Err(cycle_error(path.iter().chain(std::iter::once(&child))));
+            }
+            let mut child_node = node.child(child, quantity)?;
+            self.build_tree(&mut child_node, depth, path)?;
+            node.children.push(child_node);
+        }
+        path.pop();
+        Ok(())
+    }
+
     /// Get all parent items using the given item
     pub fn where_used(&self, pn: &str) -> Result<Vec<Item>> {
         let item = self.db.get_item_by_pn(pn)?;
         self.db.where_used(&item)
     }
 
+    /// Get all the paths from the given item up to the top level products
+    /// using it, with the effective quantity of the item along each path
+    ///
+    /// An item which is not used anywhere has no path.
+    pub fn where_used_all(&self, pn: &str) -> Result<Vec<UsagePath>> {
+        let item = self.db.get_item_by_pn(pn)?;
+        let mut paths = Vec::new();
+        for (parent, quantity) in self.db.get_parents(&item)? {
+            let quantity = quantity.to(item.unit())?;
+            let mut path = vec![item.clone(), parent];
+            self.collect_usage_paths(&mut path, quantity, &mut paths)?;
+        }
+        Ok(paths)
+    }
+
+    /// Walk up from the last item of `path`, adding a [UsagePath] for each
+    /// top level product reached
+    ///
+    /// `quantity` is the quantity of the first item of the path in one unit
+    /// of the last one.
+    fn collect_usage_paths(
+        &self,
+        path: &mut Vec<Item>,
+        quantity: f64,
+        paths: &mut Vec<UsagePath>,
+    ) -> Result<()> {
+        let item = path.last().expect("path is never empty");
+        let parents = self.db.get_parents(item)?;
+        if parents.is_empty() {
+            paths.push(UsagePath {
+                items: path.clone(),
+                quantity,
+            });
+        }
+        let unit = item.unit();
+        for (parent, parent_quantity) in parents {
+            if let Some(pos) = path.iter().position(|i| i == &parent) {
+                let cycle = std::iter::once(&parent).chain(path[pos..].iter().rev());
+                return Err(cycle_error(cycle));
+            }
+            let quantity = quantity * parent_quantity.to(unit)?;
+            path.push(parent);
+            self.collect_usage_paths(path, quantity, paths)?;
+            path.pop();
+        }
+        Ok(())
+    }
+
     /// Get all items and quantity that compose the given item
-    pub fn get_stock(&self, pn: &str) -> Result<HashMap<Item, usize>> {
+    ///
+    /// The structure is exploded on all levels : quantities are converted to
+    /// the unit of their item, multiplied by the quantity of their parent
+    /// link, and summed when an item is used in several branches. Both
+    /// intermediate and leaf items are returned.
+    pub fn get_stock(&self, pn: &str) -> Result<HashMap<Item, f64>> {
+        let item = self.db.get_item_by_pn(pn)?;
         let mut stock = HashMap::new();
-        for (child, quantity) in self.get_children(pn)? {
-            stock.extend(self.get_stock(child.pn())?);
-            *stock.entry(child).or_insert(0) += quantity;
-        }
+        self.explode(&mut vec![item], 1.0, false, &mut stock)?;
         Ok(stock)
     }
+
+    /// Get all leaf items (items without children) and quantity that
+    /// compose the given item
+    ///
+    /// See [Store::get_stock]
+    pub fn get_leaf_stock(&self, pn: &str) -> Result<HashMap<Item, f64>> {
+        let item = self.db.get_item_by_pn(pn)?;
+        let mut stock = HashMap::new();
+        self.explode(&mut vec![item], 1.0, true, &mut stock)?;
+        Ok(stock)
+    }
+
+    /// Add the children of the last item of `path` to the `stock`,
+    /// multiplied by `factor`.
+    ///
+    /// Returns `true` if the item has children
+    fn explode(
+        &self,
+        path: &mut Vec<Item>,
+        factor: f64,
+        leaves_only: bool,
+        stock: &mut HashMap<Item, f64>,
+    ) -> Result<bool> {
+        let parent = path.last().expect("path is never empty");
+        let children = self.db.get_children(parent)?;
+        let has_children = !children.is_empty();
+        for (child, quantity) in children {
+            if path.contains(&child) {
+                return Err(cycle_error(path.iter().chain(std::iter::once(&child))));
+            }
+            let quantity = quantity.to(child.unit())? * factor;
+            path.push(child);
+            let is_assembly = self.explode(path, quantity, leaves_only, stock)?;
+            let child = path.pop().expect("child pushed above");
+            if !(leaves_only && is_assembly) {
+                *stock.entry(child).or_insert(0.0) += quantity;
+            }
+        }
+        Ok(has_children)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The README example : a school with rooms, a kitchen and a restaurant
+    fn school() -> Store {
+        let mut store = Store::open(":memory:").unwrap();
+        store.new_item("L289651", "chair").unwrap();
+        store.new_item("305.294.67", "table").unwrap();
+        store.new_item("000001", "room").unwrap();
+        store.add_child("000001", "L289651", 25.0).unwrap();
+        store.add_child("000001", "305.294.67", 25.0).unwrap();
+        store.new_item("000010", "kitchen").unwrap();
+        store.new_item("000020", "restaurant").unwrap();
+        store.add_child("000020", "L289651", 80.0).unwrap();
+        store.add_child("000020", "305.294.67", 80.0).unwrap();
+        store.new_item("000100", "School").unwrap();
+        store.add_child("000100", "000001", 7.0).unwrap();
+        store.add_child("000100", "000010", 1.0).unwrap();
+        store.add_child("000100", "000020", 1.0).unwrap();
+        store
+    }
+
+    fn quantities(stock: HashMap<Item, f64>) -> HashMap<String, f64> {
+        stock
+            .into_iter()
+            .map(|(item, quantity)| (item.pn().to_string(), quantity))
+            .collect()
+    }
+
+    #[test]
+    fn stock() {
+        let store = school();
+        let stock = quantities(store.get_stock("000100").unwrap());
+        assert_eq!(5, stock.len());
+        assert_eq!(Some(&7.0), stock.get("000001"));
+        assert_eq!(Some(&1.0), stock.get("000010"));
+        assert_eq!(Some(&1.0), stock.get("000020"));
+        assert_eq!(Some(&(7.0 * 25.0 + 80.0)), stock.get("L289651"));
+        assert_eq!(Some(&(7.0 * 25.0 + 80.0)), stock.get("305.294.67"));
+    }
+
+    #[test]
+    fn leaf_stock() {
+        let store = school();
+        let stock = quantities(store.get_leaf_stock("000100").unwrap());
+        assert_eq!(3, stock.len());
+        assert_eq!(Some(&1.0), stock.get("000010"));
+        assert_eq!(Some(&255.0), stock.get("L289651"));
+        assert_eq!(Some(&255.0), stock.get("305.294.67"));
+    }
+
+    #[test]
+    fn add_child_refuses_cycles() {
+        let mut store = school();
+        match store.add_child("L289651", "000100", 1.0) {
+            Err(Error::CycleDetected(path)) => {
+                assert_eq!(path.first().map(String::as_str), Some("L289651"));
+                assert_eq!(path.get(1).map(String::as_str), Some("000100"));
+                assert_eq!(path.last().map(String::as_str), Some("L289651"));
+                assert_eq!(4, path.len());
+            }
+            _ => panic!("cycle not detected"),
+        }
+        assert!(matches!(
+            store.add_child("000010", "000010", 1.0),
+            Err(Error::CycleDetected(path)) if path == ["000010", "000010"]
+        ));
+        assert!(store.find_cycles().unwrap().is_empty());
+    }
+
+    fn pns_quantities(children: &[(Item, Quantity)]) -> Vec<(&str, f64)> {
+        children
+            .iter()
+            .map(|(item, q)| (item.pn(), q.value))
+            .collect()
+    }
+
+    #[test]
+    fn move_child() {
+        let mut store = school();
+        assert!(store.check_add_child("000010", "L289651").is_ok());
+        assert!(matches!(
+            store.check_add_child("000020", "L289651"),
+            Err(Error::ChildAlreadyLinked { .. })
+        ));
+        assert!(matches!(
+            store.check_add_child("L289651", "000100"),
+            Err(Error::CycleDetected(_))
+        ));
+        // nothing was linked
+        assert!(store.get_children("000010").unwrap().is_empty());
+
+        store.move_child("000001", "000010", "L289651").unwrap();
+        assert_eq!(1, store.get_children("000001").unwrap().len());
+        let children = store.get_children("000010").unwrap();
+        assert_eq!(vec![("L289651", 25.0)], pns_quantities(&children));
+
+        assert!(matches!(
+            store.move_child("000100", "305.294.67", "000001"),
+            Err(Error::CycleDetected(_))
+        ));
+        assert!(matches!(
+            store.move_child("000001", "000010", "L289651"),
+            Err(Error::ChildNotLinked { .. })
+        ));
+        assert_eq!(3, store.get_children("000100").unwrap().len());
+
+        // the current parent must be editable too
+        for pn in ["305.294.67", "000001"] {
+            store.set_maturity(pn, ItemMaturity::InReview).unwrap();
+            store.set_maturity(pn, ItemMaturity::Released).unwrap();
+        }
+        assert!(store.check_add_child("000010", "305.294.67").is_ok());
+        assert!(matches!(
+            store.check_move_child("000001", "000010", "305.294.67"),
+            Err(Error::FrozenItem { .. })
+        ));
+        assert!(matches!(
+            store.move_child("000001", "000010", "305.294.67"),
+            Err(Error::FrozenItem { .. })
+        ));
+    }
+
+    #[test]
+    fn find_cycles() {
+        let mut store = school();
+        // bypass the store checks to build a corrupted structure
+        let chair = store.db.get_item_by_pn("L289651").unwrap();
+        let room = store.db.get_item_by_pn("000001").unwrap();
+        store
+            .db
+            .add_child(&chair, &room, Quantity::new(1.0, Unit::Each))
+            .unwrap();
+
+        let cycles = store.find_cycles().unwrap();
+        assert_eq!(1, cycles.len());
+        let cycle = cycles[0].iter().map(|i| i.pn()).collect::<Vec<_>>();
+        assert_eq!(3, cycle.len());
+        assert_eq!(cycle.first(), cycle.last());
+        assert!(cycle.contains(&"L289651") && cycle.contains(&"000001"));
+
+        assert!(matches!(
+            store.get_stock("000100"),
+            Err(Error::CycleDetected(_))
+        ));
+    }
+
+    #[test]
+    fn tree() {
+        let store = school();
+        let tree = store.get_tree("000100", None).unwrap();
+        assert_eq!("000100", tree.item.pn());
+        assert_eq!(3, tree.children.len());
+        let nodes = tree
+            .iter()
+            .map(|n| (n.level, n.item.pn(), n.quantity.value, n.total_quantity))
+            .collect::<Vec<_>>();
+        assert_eq!(8, nodes.len());
+        assert!(nodes.contains(&(1, "000001", 7.0, 7.0)));
+        assert!(nodes.contains(&(2, "L289651", 25.0, 175.0)));
+        assert!(nodes.contains(&(2, "L289651", 80.0, 80.0)));
+        assert!(nodes.contains(&(1, "000010", 1.0, 1.0)));
+
+        let tree = store.get_tree("000100", Some(1)).unwrap();
+        assert_eq!(4, tree.iter().count());
+        let tree = store.get_tree("000100", Some(0)).unwrap();
+        assert!(tree.children.is_empty());
+    }
+
+    #[test]
+    fn where_used_all() {
+        let store = school();
+        let paths = store.where_used_all("L289651").unwrap();
+        let mut paths = paths
+            .iter()
+            .map(|p| {
+                let items = p.items.iter().map(|i| i.pn()).collect::<Vec<_>>();
+                (items, p.quantity)
+            })
+            .collect::<Vec<_>>();
+        paths.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            vec![
+                (vec!["L289651", "000001", "000100"], 175.0),
+                (vec!["L289651", "000020", "000100"], 80.0)
+            ],
+            paths
+        );
+        assert!(store.where_used_all("000100").unwrap().is_empty());
+    }
+
+    #[test]
+    fn lifecycle() {
+        use ItemMaturity::*;
+        let mut store = school();
+        assert!(matches!(
+            store.set_maturity("000001", Released),
+            Err(Error::UnreleasedChild { pn, child_pn }) if pn == "000001" && child_pn == "L289651"
+        ));
+        assert!(matches!(
+            store.set_maturity("L289651", Obsolete),
+            Err(Error::InvalidTransition {
+                from: InProgress,
+                to: Obsolete,
+                ..
+            })
+        ));
+        store.set_maturity("L289651", InReview).unwrap();
+        store.set_maturity("L289651", Released).unwrap();
+        store.set_maturity("305.294.67", Released).unwrap();
+        let room = store.set_maturity("000001", Released).unwrap();
+        assert_eq!(Released, room.maturity());
+        assert!(store.set_maturity("000001", InProgress).is_err());
+        store.set_maturity("000001", Obsolete).unwrap();
+        assert!(store.set_maturity("000001", Released).is_err());
+        let err = store.add_child("000001", "000010", 1.0).unwrap_err();
+        assert_eq!(
+            "000001-001 is obsolete and can't be modified, create a new revision instead",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn revise() {
+        let mut store = school();
+        assert!(matches!(
+            store.revise("L289651"),
+            Err(Error::NotReleased { version: 1, .. })
+        ));
+        store
+            .set_maturity("L289651", ItemMaturity::Released)
+            .unwrap();
+        store
+            .set_maturity("305.294.67", ItemMaturity::Released)
+            .unwrap();
+        store
+            .set_maturity("000001", ItemMaturity::Released)
+            .unwrap();
+
+        let room = store.revise("000001").unwrap();
+        assert_eq!(2, room.version());
+        assert_eq!(ItemMaturity::InProgress, room.maturity());
+        assert_eq!(2, store.get_children("000001").unwrap().len());
+        assert!(store.revise("000001").is_err());
+
+        let revisions = store.get_revisions("000001").unwrap();
+        assert_eq!(
+            vec![1, 2],
+            revisions.iter().map(|i| i.version()).collect::<Vec<_>>()
+        );
+        let first = store.get_item("000001", 1).unwrap();
+        assert_eq!(ItemMaturity::Released, first.maturity());
+
+        // the new revision can be changed without impacting the released one
+        store.add_child("000001", "000010", 1.0).unwrap();
+        assert_eq!(3, store.get_children("000001").unwrap().len());
+        let tree = store.get_tree("000100", Some(1)).unwrap();
+        assert!(tree.children.iter().any(|n| n.item == first));
+    }
+
+    #[test]
+    fn released_items_are_frozen() {
+        let mut store = school();
+        let mut chair = store.get_revisions("L289651").unwrap().remove(0);
+        chair.set_name("armchair");
+        store.save_item(chair.clone()).unwrap();
+        assert_eq!("armchair", store.get_item("L289651", 1).unwrap().name());
+
+        store
+            .set_maturity("L289651", ItemMaturity::Released)
+            .unwrap();
+        chair.set_name("stool");
+        assert!(matches!(
+            store.save_item(chair),
+            Err(Error::FrozenItem { version: 1, .. })
+        ));
+        assert!(matches!(
+            store.add_child("L289651", "000010", 1.0),
+            Err(Error::FrozenItem { .. })
+        ));
+
+        store.revise("L289651").unwrap();
+        let mut chair = store.get_item("L289651", 2).unwrap();
+        chair.set_name("stool");
+        store.save_item(chair).unwrap();
+        assert_eq!("armchair", store.get_item("L289651", 1).unwrap().name());
+        assert_eq!("stool", store.get_item("L289651", 2).unwrap().name());
+    }
+
+    #[test]
+    fn remove_child() {
+        let mut store = school();
+        store.set_child_quantity("000001", "L289651", 30.0).unwrap();
+        assert_eq!(
+            Some(&(7.0 * 30.0 + 80.0)),
+            quantities(store.get_stock("000100").unwrap()).get("L289651")
+        );
+        store.remove_child("000001", "L289651").unwrap();
+        assert_eq!(1, store.get_children("000001").unwrap().len());
+        assert!(matches!(
+            store.remove_child("000001", "L289651"),
+            Err(Error::ChildNotLinked { .. })
+        ));
+        assert!(store.set_child_quantity("000001", "L289651", 1.0).is_err());
+
+        store
+            .set_maturity("305.294.67", ItemMaturity::Released)
+            .unwrap();
+        store
+            .set_maturity("000001", ItemMaturity::Released)
+            .unwrap();
+        assert!(matches!(
+            store.remove_child("000001", "305.294.67"),
+            Err(Error::FrozenItem { .. })
+        ));
+        assert!(matches!(
+            store.set_child_quantity("000001", "305.294.67", 2.0),
+            Err(Error::FrozenItem { .. })
+        ));
+    }
+
+    #[test]
+    fn delete_item() {
+        let mut store = school();
+        match store.delete_item("L289651", false) {
+            Err(Error::ItemUsed { mut parents, .. }) => {
+                parents.sort();
+                assert_eq!(vec!["000001", "000020"], parents);
+            }
+            _ => panic!("used item deleted"),
+        }
+        store.delete_item("L289651", true).unwrap();
+        assert!(store.get_item("L289651", 1).is_err());
+        assert_eq!(1, store.get_children("000020").unwrap().len());
+
+        store.delete_item("000100", false).unwrap();
+        assert_eq!(4, store.get_items().unwrap().len());
+
+        // deleting a new revision cancels it
+        store
+            .set_maturity("305.294.67", ItemMaturity::Released)
+            .unwrap();
+        assert!(store.delete_item("305.294.67", true).is_err());
+        store.revise("305.294.67").unwrap();
+        store.delete_item("305.294.67", false).unwrap();
+        assert_eq!(1, store.get_item_by_pn("305.294.67").unwrap().version());
+    }
+
+    #[test]
+    fn transaction() {
+        let mut store = school();
+        let result = store.transaction(|store| {
+            store.new_item("1", "ONE")?;
+            store.add_child("000010", "1", 2.0)?;
+            store.add_child("000010", "UNKNOWN", 1.0)
+        });
+        assert!(matches!(result, Err(Error::ItemNotFound(_))));
+        assert!(store.get_item_by_pn("1").is_err());
+        assert!(store.get_children("000010").unwrap().is_empty());
+
+        let item = store
+            .transaction(|store| {
+                let item = store.new_item("1", "ONE")?;
+                // a failing nested transaction only cancels its own changes
+                let nested = store.transaction(|store| {
+                    store.new_item("2", "TWO")?;
+                    store.new_item("2", "TWO")
+                });
+                assert!(nested.is_err());
+                Ok(item)
+            })
+            .unwrap();
+        assert_eq!(item, store.get_item_by_pn("1").unwrap());
+        assert!(store.get_item_by_pn("2").is_err());
+    }
+
+    #[test]
+    fn error_messages() {
+        let mut store = school();
+        let err = store.get_item_by_pn("UNKNOWN").unwrap_err();
+        assert_eq!("item UNKNOWN not found", err.to_string());
+        let err = store.new_item("000001", "another room").unwrap_err();
+        assert_eq!("an item with the PN 000001 already exists", err.to_string());
+        let err = store.add_child("000010", "000010", 1.0).unwrap_err();
+        assert_eq!(
+            "the structure would contain a cycle : 000010 -> 000010",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn stock_of_leaf_is_empty() {
+        let store = school();
+        assert!(store.get_stock("L289651").unwrap().is_empty());
+        assert!(store.get_stock("UNKNOWN").is_err());
+    }
+
+    #[test]
+    fn serialize() {
+        let store = school();
+        let tree = serde_json::to_value(store.get_tree("000001", None).unwrap()).unwrap();
+        assert_eq!("000001", tree["item"]["pn"]);
+        assert_eq!(1, tree["item"]["version"]);
+        assert_eq!("room", tree["item"]["name"]);
+        assert_eq!("in-progress", tree["item"]["maturity"]);
+        assert_eq!(2, tree["children"].as_array().unwrap().len());
+        assert_eq!("each", tree["item"]["unit"]);
+        assert_eq!(25.0, tree["children"][0]["quantity"]["value"]);
+        assert_eq!("each", tree["children"][0]["quantity"]["unit"]);
+        assert_eq!(25.0, tree["children"][0]["total_quantity"]);
+
+        let stock = store.get_stock("000001").unwrap();
+        let (item, quantity) = stock.into_iter().next().unwrap();
+        let line = serde_json::to_value(crate::ItemQuantity::from((item, quantity))).unwrap();
+        assert_eq!(25.0, line["quantity"]);
+        assert_eq!("each", line["unit"]);
+        assert!(line["pn"] == "L289651" || line["pn"] == "305.294.67");
+    }
+
+    #[test]
+    fn search() {
+        let mut store = school();
+        let pns = |store: &Store, query: SearchQuery| {
+            store
+                .search(&query)
+                .unwrap()
+                .iter()
+                .map(|item| item.pn().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["000001"], pns(&store, SearchQuery::new("ROOM")));
+        assert_eq!(vec!["305.294.67"], pns(&store, SearchQuery::new("294")));
+        assert_eq!(
+            vec!["000010", "000020"],
+            pns(&store, SearchQuery::new("0000?0"))
+        );
+        assert_eq!(
+            vec!["000100", "L289651"],
+            pns(&store, SearchQuery::new("[cs]*"))
+        );
+        assert_eq!(6, pns(&store, SearchQuery::new("")).len());
+        assert!(pns(&store, SearchQuery::new("%")).is_empty());
+
+        store
+            .set_maturity("L289651", ItemMaturity::Released)
+            .unwrap();
+        store.revise("L289651").unwrap();
+        let released = SearchQuery {
+            maturity: Some(ItemMaturity::Released),
+            ..Default::default()
+        };
+        assert!(pns(&store, released.clone()).is_empty());
+        let first_version = SearchQuery {
+            version: Some(1),
+            ..released
+        };
+        assert_eq!(vec!["L289651"], pns(&store, first_version));
+        let second_version = SearchQuery {
+            version: Some(2),
+            ..SearchQuery::new("chair")
+        };
+        let items = store.search(&second_version).unwrap();
+        assert_eq!(1, items.len());
+        assert_eq!(2, items[0].version());
+    }
+
+    #[test]
+    fn pn_schemes() {
+        let mut store = school();
+        assert!(store.pn_provider().unwrap().is_none());
+        assert_eq!("00000001", store.create("desk").unwrap().pn());
+
+        store.set_pn_scheme("prefixed").unwrap();
+        store.new_item("ASM-000001", "bench").unwrap();
+        let item = store.create_in(Some("ASM"), "blackboard").unwrap();
+        assert_eq!("ASM-000002", item.pn());
+        let err = store.new_item("L289652", "stool").unwrap_err();
+        assert_eq!(
+            "L289652 doesn't follow the PN scheme prefixed of the store",
+            err.to_string()
+        );
+        assert!(store.create("stool").is_err());
+
+        assert!(store.set_pn_scheme("unknown").is_err());
+        assert_eq!("prefixed", store.pn_provider().unwrap().unwrap().name());
+    }
+
+    #[test]
+    fn attributes() {
+        let mut store = school();
+        let number = |n| AttributeValue::Number(n);
+        let text = |t: &str| AttributeValue::Text(t.to_string());
+        store
+            .set_attribute("L289651", "weight", &number(4.5))
+            .unwrap();
+        store
+            .set_attribute("L289651", "material", &text("Wood"))
+            .unwrap();
+        store
+            .set_attribute("305.294.67", "weight", &number(12.0))
+            .unwrap();
+        store
+            .set_attribute("305.294.67", "material", &text("steel"))
+            .unwrap();
+        let chair = store.get_item_by_pn("L289651").unwrap();
+        let attributes = store.get_attributes(&chair).unwrap();
+        assert_eq!(
+            vec![("material", &text("Wood")), ("weight", &number(4.5))],
+            attributes
+                .iter()
+                .map(|(k, v)| (k.as_str(), v))
+                .collect::<Vec<_>>()
+        );
+
+        let search = |store: &Store, attributes: &[(&str, &str)]| {
+            let query = SearchQuery {
+                attributes: attributes
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                ..Default::default()
+            };
+            let items = store.search(&query).unwrap();
+            items.iter().map(|i| i.pn().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["L289651"], search(&store, &[("material", "wood")]));
+        assert_eq!(vec!["305.294.67"], search(&store, &[("weight", "12")]));
+        assert_eq!(
+            vec!["305.294.67", "L289651"],
+            search(&store, &[("material", "[sw]*")])
+        );
+        assert!(search(&store, &[("material", "wood"), ("weight", "12")]).is_empty());
+        assert!(search(&store, &[("color", "wood")]).is_empty());
+
+        // the attributes are kept by the new revisions
+        store
+            .set_maturity("L289651", ItemMaturity::Released)
+            .unwrap();
+        assert!(matches!(
+            store.set_attribute("L289651", "weight", &number(5.0)),
+            Err(Error::FrozenItem { .. })
+        ));
+        let revision = store.revise("L289651").unwrap();
+        assert_eq!(attributes, store.get_attributes(&revision).unwrap());
+        store.remove_attribute("L289651", "weight").unwrap();
+        assert_eq!(1, store.get_attributes(&revision).unwrap().len());
+        assert_eq!(2, store.get_attributes(&chair).unwrap().len());
+        let err = store.remove_attribute("L289651", "weight").unwrap_err();
+        assert_eq!("L289651 has no attribute \"weight\"", err.to_string());
+    }
+
+    #[test]
+    fn save_as() {
+        let store = school();
+        let path = std::env::temp_dir().join(format!("pbs_save_as_{}.db3", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        store.save_as(path).unwrap();
+        // the copy can't overwrite a file
+        assert!(store.save_as(path).is_err());
+        let copy = Store::open_existing(path).unwrap();
+        assert_eq!(store.get_items().unwrap(), copy.get_items().unwrap());
+        assert_eq!(
+            store.get_stock("000100").unwrap(),
+            copy.get_stock("000100").unwrap()
+        );
+        drop(copy);
+        std::fs::remove_file(path).unwrap();
+        // a deleted store isn't created again
+        assert!(Store::open_existing(path).is_err());
+        assert!(!std::path::Path::new(path).exists());
+    }
+
+    #[test]
+    fn units() {
+        let mut store = school();
+        // each chair has a 1.5 m cable for its lamp, the cable being bought
+        // by the metre, and the rooms are painted with 2 L of paint
+        store.new_item("C1", "cable").unwrap();
+        store.set_unit("C1", Unit::Metre).unwrap();
+        store
+            .add_child_in("L289651", "C1", 1500.0, Unit::Millimetre)
+            .unwrap();
+        store.new_item("P1", "paint").unwrap();
+        store.set_unit("P1", Unit::Litre).unwrap();
+        store.add_child("000001", "P1", 2.0).unwrap();
+        assert_eq!(
+            vec![Quantity::new(1500.0, Unit::Millimetre)],
+            store
+                .get_children("L289651")
+                .unwrap()
+                .into_iter()
+                .map(|(_, q)| q)
+                .collect::<Vec<_>>()
+        );
+
+        let stock = quantities(store.get_stock("000100").unwrap());
+        assert_eq!(Some(&(255.0 * 1.5)), stock.get("C1"));
+        assert_eq!(Some(&14.0), stock.get("P1"));
+        let paths = store.where_used_all("C1").unwrap();
+        let mut used = paths.iter().map(|p| p.quantity).collect::<Vec<_>>();
+        used.sort_by(|a, b| a.total_cmp(b));
+        assert_eq!(vec![1.5 * 80.0, 1.5 * 175.0], used);
+
+        // the units must be compatible
+        assert!(matches!(
+            store.add_child_in("000010", "P1", 1.0, Unit::Kilogram),
+            Err(Error::IncompatibleUnits {
+                from: Unit::Kilogram,
+                to: Unit::Litre
+            })
+        ));
+        assert!(matches!(
+            store.add_child("000010", "P1", 0.0),
+            Err(Error::InvalidQuantity(_))
+        ));
+        assert!(store.set_unit("C1", Unit::Kilogram).is_err());
+        store.set_unit("C1", Unit::Centimetre).unwrap();
+        let stock = quantities(store.get_stock("L289651").unwrap());
+        assert_eq!(Some(&150.0), stock.get("C1"));
+
+        // changing the quantity keeps the unit of the link
+        store.set_child_quantity("L289651", "C1", 2000.0).unwrap();
+        let stock = quantities(store.get_stock("L289651").unwrap());
+        assert_eq!(Some(&200.0), stock.get("C1"));
+        store
+            .set_child_quantity_in("L289651", "C1", 1.0, Unit::Metre)
+            .unwrap();
+        let stock = quantities(store.get_stock("L289651").unwrap());
+        assert_eq!(Some(&100.0), stock.get("C1"));
+    }
 }
@@ -0,0 +1,111 @@
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{Item, Quantity, Result};
+
+/// A node of the multi-level product structure, as returned by
+/// [crate::Store::get_tree]
+#[derive(Serialize)]
+pub struct TreeNode {
+    /// The item of this node
+    pub item: Item,
+    /// Level of the node, the root being at level 0
+    pub level: usize,
+    /// Quantity of the item in its parent (1 for the root)
+    pub quantity: Quantity,
+    /// Quantity of the item in the root, in the unit of the item, i.e. the
+    /// product of all the quantities from the root to this node
+    pub total_quantity: f64,
+    /// Children of the item, empty for a leaf or when the depth limit is
+    /// reached
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    pub(crate) fn root(item: Item) -> Self {
+        TreeNode {
+            quantity: Quantity::new(1.0, item.unit()),
+            item,
+            level: 0,
+            total_quantity: 1.0,
+            children: Vec::new(),
+        }
+    }
+
+    /// A child node, which fails if the quantity of the link can't be
+    /// converted to the unit of the child
+    pub(crate) fn child(&self, item: Item, quantity: Quantity) -> Result<Self> {
+        Ok(TreeNode {
+            total_quantity: self.total_quantity * quantity.to(item.unit())?,
+            item,
+            level: self.level + 1,
+            quantity,
+            children: Vec::new(),
+        })
+    }
+
+    /// Iterate over all the nodes of the tree, depth first, starting with
+    /// this node
+    pub fn iter(&self) -> impl Iterator<Item = &TreeNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
+
+/// A path going up the product structure, from an item to one of the top
+/// level products using it, as returned by [crate::Store::where_used_all]
+#[derive(Serialize)]
+pub struct UsagePath {
+    /// Items of the path, starting with the used item and ending with the
+    /// top level product
+    pub items: Vec<Item>,
+    /// Quantity of the used item in the top level product along this path,
+    /// in the unit of the used item
+    pub quantity: f64,
+}
+
+impl UsagePath {
+    /// The top level product of the path
+    pub fn root(&self) -> &Item {
+        self.items.last().expect("a usage path is never empty")
+    }
+}
+
+/// An item with a quantity, such as a child link of a parent item or an
+/// entry of the stock of an item
+pub struct ItemQuantity {
+    pub item: Item,
+    pub quantity: Quantity,
+}
+
+impl From<(Item, Quantity)> for ItemQuantity {
+    fn from((item, quantity): (Item, Quantity)) -> Self {
+        ItemQuantity { item, quantity }
+    }
+}
+
+/// An entry of the stock, in the unit of the item
+impl From<(Item, f64)> for ItemQuantity {
+    fn from((item, quantity): (Item, f64)) -> Self {
+        let quantity = Quantity::new(quantity, item.unit());
+        ItemQuantity { item, quantity }
+    }
+}
+
+/// Serialized as a flat structure : the fields of the [Item] except its
+/// unit, followed by the `quantity` and its `unit`
+impl Serialize for ItemQuantity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut line = serializer.serialize_struct("ItemQuantity", 6)?;
+        line.serialize_field("pn", self.item.pn())?;
+        line.serialize_field("version", &self.item.version())?;
+        line.serialize_field("name", self.item.name())?;
+        line.serialize_field("maturity", &self.item.maturity())?;
+        line.serialize_field("quantity", &self.quantity.value)?;
+        line.serialize_field("unit", &self.quantity.unit)?;
+        line.end()
+    }
+}
@@ -0,0 +1,284 @@
+//! Units of measure of the items, e.g. a cable bought in metres or paint in
+//! litres, and the quantities of the links expressed in these units
+
+use rusqlite::{
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+    ToSql,
+};
+use serde::{Serialize, Serializer};
+
+use crate::{Error, Result};
+
+/// What a unit measures : only the units of the same dimension can be
+/// converted to each other
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Dimension {
+    Count,
+    Length,
+    Area,
+    Volume,
+    Mass,
+}
+
+/// Unit of measure of an item, in which its quantities are counted
+///
+/// The symbols are stored in the database and must not change
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// Items counted one by one, e.g. screws
+    #[default]
+    Each,
+    Millimetre,
+    Centimetre,
+    Metre,
+    SquareMetre,
+    Millilitre,
+    Litre,
+    CubicMetre,
+    Gram,
+    Kilogram,
+    Tonne,
+}
+
+impl Unit {
+    /// All the units, by dimension
+    pub const ALL: [Unit; 11] = [
+        Unit::Each,
+        Unit::Millimetre,
+        Unit::Centimetre,
+        Unit::Metre,
+        Unit::SquareMetre,
+        Unit::Millilitre,
+        Unit::Litre,
+        Unit::CubicMetre,
+        Unit::Gram,
+        Unit::Kilogram,
+        Unit::Tonne,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Each => "each",
+            Unit::Millimetre => "mm",
+            Unit::Centimetre => "cm",
+            Unit::Metre => "m",
+            Unit::SquareMetre => "m2",
+            Unit::Millilitre => "mL",
+            Unit::Litre => "L",
+            Unit::CubicMetre => "m3",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Tonne => "t",
+        }
+    }
+
+    /// Get a unit by its symbol, litres being also accepted as `l` and `ml`
+    pub fn parse(symbol: &str) -> Result<Unit> {
+        match symbol {
+            "l" => Ok(Unit::Litre),
+            "ml" => Ok(Unit::Millilitre),
+            _ => Unit::ALL
+                .into_iter()
+                .find(|unit| unit.symbol() == symbol)
+                .ok_or_else(|| Error::UnknownUnit(symbol.to_string())),
+        }
+    }
+
+    fn dimension(self) -> Dimension {
+        match self {
+            Unit::Each => Dimension::Count,
+            Unit::Millimetre | Unit::Centimetre | Unit::Metre => Dimension::Length,
+            Unit::SquareMetre => Dimension::Area,
+            Unit::Millilitre | Unit::Litre | Unit::CubicMetre => Dimension::Volume,
+            Unit::Gram | Unit::Kilogram | Unit::Tonne => Dimension::Mass,
+        }
+    }
+
+    /// Value of the unit in the reference unit of its dimension : each,
+    /// metre, square metre, litre or kilogram
+    fn factor(self) -> f64 {
+        match self {
+            Unit::Each | Unit::Metre | Unit::SquareMetre | Unit::Litre | Unit::Kilogram => 1.0,
+            Unit::Millimetre => 0.001,
+            Unit::Centimetre => 0.01,
+            Unit::Millilitre => 0.001,
+            Unit::CubicMetre => 1000.0,
+            Unit::Gram => 0.001,
+            Unit::Tonne => 1000.0,
+        }
+    }
+
+    /// Check if a quantity in this unit can be converted to the other unit
+    pub fn is_compatible(self, other: Unit) -> bool {
+        self.dimension() == other.dimension()
+    }
+
+    /// Convert a `value` in this unit to the unit `to`
+    pub fn convert(self, value: f64, to: Unit) -> Result<f64> {
+        if !self.is_compatible(to) {
+            return Err(Error::IncompatibleUnits { from: self, to });
+        }
+        if self == to {
+            return Ok(value);
+        }
+        Ok(value * self.factor() / to.factor())
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FromSql for Unit {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Unit::parse(value.as_str()?).map_err(|_| FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for Unit {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.symbol()))
+    }
+}
+
+/// Serialized as its symbol
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.symbol())
+    }
+}
+
+/// A quantity of a link between a parent item and a child item, expressed
+/// in a unit compatible with the unit of the child
+///
+/// It is serialized as a structure with its `value` and `unit`.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Quantity { value, unit }
+    }
+
+    /// The value of the quantity in another unit
+    pub fn to(&self, unit: Unit) -> Result<f64> {
+        self.unit.convert(self.value, unit)
+    }
+}
+
+/// Parse the quantity of a link, written as a plain decimal number followed
+/// by an optional unit, e.g. `25`, `2.5 m` or `500mm`
+///
+/// The unit is `None` when it is not given, i.e. the quantity is in the
+/// unit of the child item. The exponent notation, e.g. `1e3`, is refused.
+pub fn parse_link_quantity(text: &str) -> Result<(f64, Option<Unit>)> {
+    let text = text.trim();
+    let invalid = || Error::InvalidQuantity(text.to_string());
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (value, unit) = text.split_at(split);
+    let value = value.parse::<f64>().map_err(|_| invalid())?;
+    check_quantity(value)?;
+    // `e` followed by digits is an exponent, and not the start of `each`
+    let exponent = unit
+        .strip_prefix(['e', 'E'])
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| c.is_ascii_digit() || c == '+' || c == '-');
+    if exponent {
+        return Err(invalid());
+    }
+    let unit = match unit.trim() {
+        "" => None,
+        unit => Some(Unit::parse(unit)?),
+    };
+    Ok((value, unit))
+}
+
+/// Shown as the value followed by the unit, except for items counted one by
+/// one
+impl std::fmt::Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = format_value(self.value);
+        match self.unit {
+            Unit::Each => write!(f, "{value}"),
+            unit => write!(f, "{value} {unit}"),
+        }
+    }
+}
+
+/// Check that the quantity of a link is a positive number
+pub(crate) fn check_quantity(value: f64) -> Result<()> {
+    if !(value.is_finite() && value > 0.0) {
+        return Err(Error::InvalidQuantity(value.to_string()));
+    }
+    Ok(())
+}
+
+/// Format a computed quantity, without the rounding errors of the
+/// conversions and multiplications, e.g. `0.3` instead of
+/// `0.30000000000000004`
+pub fn format_value(value: f64) -> String {
+    let rounded = (value * 1e9).round() / 1e9;
+    format!("{rounded}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn convert() {
+        assert_eq!(0.5, Unit::Millimetre.convert(500.0, Unit::Metre).unwrap());
+        assert_eq!(2500.0, Unit::Kilogram.convert(2.5, Unit::Gram).unwrap());
+        assert_eq!(1500.0, Unit::CubicMetre.convert(1.5, Unit::Litre).unwrap());
+        assert_eq!(3.0, Unit::Each.convert(3.0, Unit::Each).unwrap());
+        let err = Unit::Metre.convert(1.0, Unit::Kilogram).unwrap_err();
+        assert_eq!("m can't be converted to kg", err.to_string());
+        assert!(Unit::Each.convert(1.0, Unit::Metre).is_err());
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Unit::Litre, Unit::parse("L").unwrap());
+        assert_eq!(Unit::Litre, Unit::parse("l").unwrap());
+        assert!(matches!(Unit::parse("ft"), Err(Error::UnknownUnit(_))));
+        assert_eq!((25.0, None), parse_link_quantity("25").unwrap());
+        assert_eq!(
+            (2.5, Some(Unit::Metre)),
+            parse_link_quantity("2.5 m").unwrap()
+        );
+        assert_eq!(
+            (500.0, Some(Unit::Millimetre)),
+            parse_link_quantity("500mm").unwrap()
+        );
+        assert_eq!(
+            (3.0, Some(Unit::Each)),
+            parse_link_quantity("3each").unwrap()
+        );
+        assert!(parse_link_quantity("0").is_err());
+        assert!(parse_link_quantity("m").is_err());
+        assert!(parse_link_quantity("2 ft").is_err());
+        let err = parse_link_quantity("1e3").unwrap_err();
+        assert_eq!(
+            "invalid quantity \"1e3\", it must be a positive number",
+            err.to_string()
+        );
+        assert!(matches!(
+            parse_link_quantity("2E-1 m"),
+            Err(Error::InvalidQuantity(_))
+        ));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("25", Quantity::new(25.0, Unit::Each).to_string());
+        assert_eq!("0.3 m", Quantity::new(0.1 * 3.0, Unit::Metre).to_string());
+        assert_eq!("2.5 kg", Quantity::new(2.5, Unit::Kilogram).to_string());
+    }
+}
@@ -0,0 +1,41 @@
+//! Drag and drop of items, from the search list or the tree to a node of
+//! the tree
+
+use eframe::egui;
+use egui::{CursorIcon, Id, Rect, Sense};
+
+/// An item being dragged
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DragPayload {
+    pub pn: String,
+    /// Parent of the item when it is dragged from the tree, in which case
+    /// it is moved instead of added
+    pub parent: Option<String>,
+}
+
+/// Make the area `rect` draggable, `label` following the pointer while it
+/// is dragged
+///
+/// The area can still be clicked, e.g. when it is a selectable label.
+pub fn drag_source(ui: &mut egui::Ui, id: Id, rect: Rect, label: &str) -> egui::Response {
+    let response = ui.interact(rect, id, Sense::drag());
+    if response.dragged() {
+        ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
+        egui::show_tooltip_at_pointer(ui.ctx(), id.with("dragged"), |ui| {
+            ui.label(label);
+        });
+    } else if response.hovered() {
+        ui.ctx().set_cursor_icon(CursorIcon::Grab);
+    }
+    response
+}
+
+/// Whether the item being dragged can be dropped on the hovered node of
+/// the tree, checked once per node instead of at each frame
+pub struct DropCheck {
+    pub payload: DragPayload,
+    /// PN of the hovered node
+    pub target: String,
+    /// The error preventing the drop, e.g. a cycle
+    pub result: Result<(), String>,
+}
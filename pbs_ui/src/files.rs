@@ -0,0 +1,82 @@
+//! Files of the stores : the file pickers and the stores opened recently
+
+use std::path::{Path, PathBuf};
+
+/// Key of the recent stores in the storage of the app
+const RECENT_KEY: &str = "recent_stores";
+/// Number of stores kept in the recent list
+const RECENT_MAX: usize = 10;
+
+/// Filter of the file pickers
+const EXTENSIONS: [&str; 2] = ["db3", "sqlite"];
+
+/// Stores opened recently, the last one first
+///
+/// They are saved in the storage of the app as one path per line.
+#[derive(Default)]
+pub struct RecentStores(Vec<String>);
+
+impl RecentStores {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let paths = storage
+            .and_then(|storage| storage.get_string(RECENT_KEY))
+            .unwrap_or_default();
+        RecentStores(paths.lines().map(str::to_string).collect())
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(RECENT_KEY, self.0.join("\n"));
+    }
+
+    pub fn paths(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Move a store at the top of the list
+    pub fn push(&mut self, path: &str) {
+        self.remove(path);
+        self.0.insert(0, path.to_string());
+        self.0.truncate(RECENT_MAX);
+    }
+
+    /// Forget a store, e.g. which can't be opened anymore
+    pub fn remove(&mut self, path: &str) {
+        self.0.retain(|p| p != path);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Ask for an existing store
+pub fn pick_store() -> Option<String> {
+    rfd::FileDialog::new()
+        .set_title("Open store")
+        .add_filter("Store", &EXTENSIONS)
+        .pick_file()
+        .map(path_string)
+}
+
+/// Ask for the file of a new store, the replacement of an existing file
+/// being confirmed by the file picker
+pub fn pick_new_store(title: &str) -> Option<String> {
+    rfd::FileDialog::new()
+        .set_title(title)
+        .add_filter("Store", &EXTENSIONS)
+        .set_file_name("store.db3")
+        .save_file()
+        .map(path_string)
+}
+
+fn path_string(path: PathBuf) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Name of a store shown to the user, i.e. its file name
+pub fn store_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
@@ -0,0 +1,317 @@
+//! Forms to create and modify the items, the errors of the store being shown
+//! in the form which caused them
+
+use eframe::egui;
+use egui::{Button, Color32, DragValue, RichText, TextEdit};
+use pbs_core::{Item, ItemMaturity, Result, Store, Unit};
+
+use crate::tree_view::maturity_badge;
+
+/// Smallest quantity of a link which can be entered
+const MIN_QUANTITY: f64 = 0.001;
+
+/// Field of a link quantity, followed by its unit
+fn quantity_value(value: &mut f64, unit: Option<Unit>) -> DragValue<'_> {
+    let drag = DragValue::new(value)
+        .speed(0.1)
+        .clamp_range(MIN_QUANTITY..=f64::MAX);
+    match unit {
+        Some(Unit::Each) | None => drag,
+        Some(unit) => drag.suffix(format!(" {unit}")),
+    }
+}
+
+/// Show the error of the last action of a form, if any
+fn error_label(ui: &mut egui::Ui, error: &Option<String>) {
+    if let Some(error) = error {
+        ui.colored_label(Color32::RED, error.as_str());
+    }
+}
+
+/// Keep the error of an action, and tell if the store was changed
+fn check(result: Option<Result<()>>, error: &mut Option<String>) -> bool {
+    match result {
+        Some(Ok(())) => {
+            *error = None;
+            true
+        }
+        Some(Err(e)) => {
+            *error = Some(e.to_string());
+            false
+        }
+        None => false,
+    }
+}
+
+/// Window to create an item, with a given PN or with a PN allocated by the
+/// PN scheme of the store
+#[derive(Default)]
+pub struct NewItemForm {
+    pub open: bool,
+    pn: String,
+    category: String,
+    name: String,
+    error: Option<String>,
+}
+
+impl NewItemForm {
+    /// Show the window if it is open, and return the item created
+    pub fn show(&mut self, ctx: &egui::Context, store: &mut Store) -> Option<Item> {
+        let mut open = self.open;
+        let mut created = None;
+        egui::Window::new("New item")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("new item").num_columns(2).show(ui, |ui| {
+                    ui.label("PN");
+                    ui.add(TextEdit::singleline(&mut self.pn).hint_text("allocated by the store"));
+                    ui.end_row();
+                    ui.label("Category");
+                    let category = TextEdit::singleline(&mut self.category)
+                        .hint_text("for the prefixed PN scheme");
+                    ui.add_enabled(self.pn.trim().is_empty(), category);
+                    ui.end_row();
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut self.name);
+                    ui.end_row();
+                });
+                error_label(ui, &self.error);
+                let create = Button::new("Create");
+                if ui
+                    .add_enabled(!self.name.trim().is_empty(), create)
+                    .clicked()
+                {
+                    match self.create(store) {
+                        Ok(item) => created = Some(item),
+                        Err(e) => self.error = Some(e.to_string()),
+                    }
+                }
+            });
+        if created.is_some() {
+            *self = NewItemForm::default();
+        } else {
+            self.open = open;
+        }
+        created
+    }
+
+    fn create(&self, store: &mut Store) -> Result<Item> {
+        let name = self.name.trim();
+        match (self.pn.trim(), self.category.trim()) {
+            ("", "") => store.create(name),
+            ("", category) => store.create_in(Some(category), name),
+            (pn, _) => store.new_item(pn, name),
+        }
+    }
+}
+
+/// Maturity changes proposed for an item, when they are allowed
+const TRANSITIONS: [(ItemMaturity, &str); 4] = [
+    (ItemMaturity::InReview, "Submit for review"),
+    (ItemMaturity::InProgress, "Back to in progress"),
+    (ItemMaturity::Released, "Release"),
+    (ItemMaturity::Obsolete, "Make obsolete"),
+];
+
+/// Form to modify the selected item : its name, its maturity, its unit and
+/// its children
+pub struct ItemForm {
+    pn: String,
+    name: String,
+    child_pn: String,
+    quantity: f64,
+    /// Quantity of a child being edited, saved once the edit is over
+    edited_quantity: Option<(String, f64)>,
+    error: Option<String>,
+}
+
+impl ItemForm {
+    pub fn new(item: &Item) -> Self {
+        ItemForm {
+            pn: item.pn().to_string(),
+            name: item.name().to_string(),
+            child_pn: String::new(),
+            quantity: 1.0,
+            edited_quantity: None,
+            error: None,
+        }
+    }
+
+    /// Show the form, and return `true` if the store was changed
+    pub fn ui(&mut self, ui: &mut egui::Ui, store: &mut Store) -> bool {
+        let item = match store.get_item_by_pn(&self.pn) {
+            Ok(item) => item,
+            Err(e) => {
+                ui.colored_label(Color32::RED, e.to_string());
+                return false;
+            }
+        };
+        let mut result = None;
+        ui.horizontal(|ui| {
+            ui.heading(format!("{}-{:03}", item.pn(), item.version()));
+            maturity_badge(ui, item.maturity());
+        });
+
+        // maturity
+        ui.horizontal_wrapped(|ui| {
+            for (maturity, label) in TRANSITIONS {
+                if item.maturity().can_become(maturity) && ui.button(label).clicked() {
+                    result = Some(store.set_maturity(&self.pn, maturity).map(|_| ()));
+                }
+            }
+            if item.maturity() == ItemMaturity::Released && ui.button("New revision").clicked() {
+                result = Some(store.revise(&self.pn).map(|_| ()));
+            }
+        });
+        ui.separator();
+
+        // a released item can only be modified through a new revision
+        ui.add_enabled_ui(!item.maturity().is_frozen(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut self.name);
+                let name = self.name.trim();
+                let rename = Button::new("Rename");
+                if ui
+                    .add_enabled(!name.is_empty() && name != item.name(), rename)
+                    .clicked()
+                {
+                    let mut item = item.clone();
+                    item.set_name(name);
+                    result = Some(store.save_item(item));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Unit");
+                egui::ComboBox::from_id_source("unit")
+                    .selected_text(item.unit().symbol())
+                    .show_ui(ui, |ui| {
+                        for unit in Unit::ALL {
+                            let selected = unit == item.unit();
+                            if ui.selectable_label(selected, unit.symbol()).clicked() && !selected {
+                                result = Some(store.set_unit(&self.pn, unit));
+                            }
+                        }
+                    });
+            });
+            ui.separator();
+
+            ui.label(RichText::new("Children").strong());
+            match store.get_children(&self.pn) {
+                Ok(children) => {
+                    egui::Grid::new("children").striped(true).show(ui, |ui| {
+                        for (child, quantity) in children {
+                            ui.label(child.pn());
+                            ui.label(child.name());
+                            // the quantity is only saved at the end of the edit,
+                            // and not at each step of a drag
+                            let mut value = match &self.edited_quantity {
+                                Some((pn, value)) if pn == child.pn() => *value,
+                                _ => quantity.value,
+                            };
+                            let response = ui.add(quantity_value(&mut value, Some(quantity.unit)));
+                            if response.changed() {
+                                self.edited_quantity = Some((child.pn().to_string(), value));
+                            }
+                            if response.drag_released() || response.lost_focus() {
+                                if let Some((pn, value)) = self.edited_quantity.take() {
+                                    result = Some(store.set_child_quantity(&self.pn, &pn, value));
+                                }
+                            }
+                            if ui.small_button("Remove").clicked() {
+                                result = Some(store.remove_child(&self.pn, child.pn()));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                }
+                Err(e) => {
+                    ui.colored_label(Color32::RED, e.to_string());
+                }
+            }
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.child_pn).hint_text("child PN"));
+                let child_pn = self.child_pn.trim();
+                let unit = store
+                    .get_item_by_pn(child_pn)
+                    .ok()
+                    .map(|child| child.unit());
+                ui.add(quantity_value(&mut self.quantity, unit));
+                if ui
+                    .add_enabled(!child_pn.is_empty(), Button::new("Add child"))
+                    .clicked()
+                {
+                    result = Some(store.add_child(&self.pn, child_pn, self.quantity));
+                    if matches!(result, Some(Ok(()))) {
+                        self.child_pn.clear();
+                        self.quantity = 1.0;
+                    }
+                }
+            });
+        });
+        let changed = check(result, &mut self.error);
+        error_label(ui, &self.error);
+        changed
+    }
+}
+
+/// Window asking the quantity of an item dropped on a node of the tree,
+/// before adding it as a child
+pub struct LinkForm {
+    parent_pn: String,
+    child_pn: String,
+    quantity: f64,
+    error: Option<String>,
+}
+
+impl LinkForm {
+    pub fn new(parent_pn: &str, child_pn: &str) -> Self {
+        LinkForm {
+            parent_pn: parent_pn.to_string(),
+            child_pn: child_pn.to_string(),
+            quantity: 1.0,
+            error: None,
+        }
+    }
+
+    /// Show the window, and return `Some(true)` once the child is added, or
+    /// `Some(false)` if it is cancelled
+    pub fn show(&mut self, ctx: &egui::Context, store: &mut Store) -> Option<bool> {
+        let mut done = None;
+        let mut open = true;
+        egui::Window::new("Add child")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Add {} to {}", self.child_pn, self.parent_pn));
+                let unit = store
+                    .get_item_by_pn(&self.child_pn)
+                    .ok()
+                    .map(|child| child.unit());
+                ui.horizontal(|ui| {
+                    ui.label("Quantity");
+                    ui.add(quantity_value(&mut self.quantity, unit));
+                });
+                error_label(ui, &self.error);
+                ui.horizontal(|ui| {
+                    if ui.button("Add").clicked() {
+                        let result =
+                            store.add_child(&self.parent_pn, &self.child_pn, self.quantity);
+                        if check(Some(result), &mut self.error) {
+                            done = Some(true);
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        done = Some(false);
+                    }
+                });
+            });
+        if !open {
+            done = Some(false);
+        }
+        done
+    }
+}
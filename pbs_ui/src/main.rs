@@ -1,74 +1,457 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+use dnd::{drag_source, DragPayload, DropCheck};
 use eframe::egui;
-use egui::{ScrollArea, Sense, Vec2};
-use pbs_core::{Item, Store};
+use egui::{Align2, Button, Color32, Id, ScrollArea};
+use files::{pick_new_store, pick_store, store_name, RecentStores};
+use forms::{ItemForm, LinkForm, NewItemForm};
+use pbs_core::{Item, SearchQuery, Store, TreeNode};
+use tree_view::{TreeResponse, TreeView};
+
+mod dnd;
+mod files;
+mod forms;
+mod tree_view;
+
+/// Name of the app, in the window title
+const APP_NAME: &str = "PBS";
 
 fn main() -> Result<(), eframe::Error> {
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(320.0, 240.0)),
+        initial_window_size: Some(egui::vec2(960.0, 640.0)),
         ..Default::default()
     };
-    eframe::run_native(
-        "My egui App",
-        options,
-        Box::new(|_cc| Box::<MyApp>::default()),
-    )
+    eframe::run_native(APP_NAME, options, Box::new(|cc| Box::new(MyApp::new(cc))))
+}
+
+/// Action of the File menu, run once the menu is closed
+enum FileAction {
+    New,
+    Open(Option<String>),
+    SaveAs,
 }
 
 struct MyApp {
+    /// The open store, if any
+    view: Option<StoreView>,
+    recent: RecentStores,
+    /// Error shown in a dialog until it is dismissed
+    error: Option<String>,
+    /// Title of the window, set when the open store changes
+    title: String,
+}
+
+impl MyApp {
+    /// Reopen the last store used, if any
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = MyApp {
+            view: None,
+            recent: RecentStores::load(cc.storage),
+            error: None,
+            title: String::new(),
+        };
+        if let Some(path) = app.recent.paths().first().cloned() {
+            app.open(&path);
+        }
+        app
+    }
+
+    /// Open an existing store, which replaces the open one unless it fails
+    fn open(&mut self, path: &str) {
+        self.show_store(path, Store::open_existing(path));
+    }
+
+    /// Show an opened store, or forget it if it can't be opened
+    fn show_store(&mut self, path: &str, store: pbs_core::Result<Store>) {
+        match store {
+            Ok(store) => {
+                self.view = Some(StoreView::new(store, path));
+                self.recent.push(path);
+            }
+            Err(e) => {
+                self.error = Some(format!("Cannot open {path} : {e}"));
+                self.recent.remove(path);
+            }
+        }
+    }
+
+    /// Create an empty store, replacing the file if the user agreed to it
+    fn new_store(&mut self) {
+        let Some(path) = pick_new_store("New store") else {
+            return;
+        };
+        if self.view.as_ref().is_some_and(|view| view.path == path) {
+            self.view = None;
+        }
+        if let Err(e) = remove_existing(&path) {
+            self.error = Some(format!("Cannot replace {path} : {e}"));
+            return;
+        }
+        self.show_store(&path, Store::open(&path));
+    }
+
+    /// Save a copy of the open store, and continue with the copy
+    fn save_as(&mut self) {
+        let Some(view) = &self.view else {
+            return;
+        };
+        let Some(path) = pick_new_store("Save store as") else {
+            return;
+        };
+        if view.path == path {
+            return;
+        }
+        let result = remove_existing(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|()| view.store.save_as(&path).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => self.open(&path),
+            Err(e) => self.error = Some(format!("Cannot save {path} : {e}")),
+        }
+    }
+
+    fn menu_ui(&mut self, ui: &mut egui::Ui) -> Option<FileAction> {
+        let mut action = None;
+        egui::menu::bar(ui, |ui| {
+            ui.menu_button("File", |ui| {
+                if ui.button("New...").clicked() {
+                    action = Some(FileAction::New);
+                }
+                if ui.button("Open...").clicked() {
+                    action = Some(FileAction::Open(None));
+                }
+                let save_as = Button::new("Save as...");
+                if ui.add_enabled(self.view.is_some(), save_as).clicked() {
+                    action = Some(FileAction::SaveAs);
+                }
+                ui.separator();
+                ui.add_enabled_ui(!self.recent.paths().is_empty(), |ui| {
+                    ui.menu_button("Open recent", |ui| {
+                        for path in self.recent.paths() {
+                            if ui.button(path).clicked() {
+                                action = Some(FileAction::Open(Some(path.clone())));
+                            }
+                        }
+                        ui.separator();
+                        if ui.button("Clear list").clicked() {
+                            self.recent.clear();
+                            ui.close_menu();
+                        }
+                    });
+                });
+                if action.is_some() {
+                    ui.close_menu();
+                }
+            });
+        });
+        action
+    }
+
+    fn error_ui(&mut self, ctx: &egui::Context) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new("Error")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.colored_label(Color32::RED, error.as_str());
+                close = ui.button("OK").clicked();
+            });
+        if close {
+            self.error = None;
+        }
+    }
+}
+
+impl eframe::App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let action = egui::TopBottomPanel::top("MENU")
+            .show(ctx, |ui| self.menu_ui(ui))
+            .inner;
+        match action {
+            Some(FileAction::New) => self.new_store(),
+            Some(FileAction::Open(None)) => {
+                if let Some(path) = pick_store() {
+                    self.open(&path);
+                }
+            }
+            Some(FileAction::Open(Some(path))) => self.open(&path),
+            Some(FileAction::SaveAs) => self.save_as(),
+            None => (),
+        }
+
+        let title = match &self.view {
+            Some(view) => format!("{} - {APP_NAME}", store_name(&view.path)),
+            None => APP_NAME.to_string(),
+        };
+        if title != self.title {
+            frame.set_window_title(&title);
+            self.title = title;
+        }
+
+        match &mut self.view {
+            Some(view) => view.ui(ctx),
+            None => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.label("Create or open a store from the File menu");
+                });
+            }
+        }
+        self.error_ui(ctx);
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.recent.save(storage);
+    }
+}
+
+/// Remove a file before it is replaced by a store, if it exists
+fn remove_existing(path: &str) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// The open store : the search list, the tree of the selected item and its
+/// form
+struct StoreView {
     store: Store,
+    /// File of the store
+    path: String,
     search_pattern: String,
+    /// PN of the item selected in the side panel
+    selected: Option<String>,
+    /// Tree of the selected item, or the error preventing to get it
+    tree: Option<Result<TreeNode, String>>,
+    new_item: NewItemForm,
+    /// Form of the selected item
+    item_form: Option<ItemForm>,
+    /// Item dragged from the search list or the tree
+    dragged: Option<DragPayload>,
+    /// Whether the dragged item can be dropped on the hovered node
+    drop_check: Option<DropCheck>,
+    /// Quantity of an item dropped on the tree
+    link_form: Option<LinkForm>,
+    /// Error of the last move made in the tree
+    tree_error: Option<String>,
 }
 
-impl Default for MyApp {
-    fn default() -> Self {
-        Self {
-            store: Store::open("store.db3").unwrap(),
+impl StoreView {
+    fn new(store: Store, path: &str) -> Self {
+        StoreView {
+            store,
+            path: path.to_string(),
             search_pattern: String::new(),
+            selected: None,
+            tree: None,
+            new_item: NewItemForm::default(),
+            item_form: None,
+            dragged: None,
+            drop_check: None,
+            link_form: None,
+            tree_error: None,
         }
     }
-}
 
-impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let items = self.store.get_items().unwrap();
+    /// Select an item and load its tree
+    fn select(&mut self, pn: &str) {
+        self.selected = Some(pn.to_string());
+        self.item_form = self
+            .store
+            .get_item_by_pn(pn)
+            .ok()
+            .map(|i| ItemForm::new(&i));
+        self.reload_tree();
+    }
+
+    /// Load the tree of the selected item, after a change of the store
+    fn reload_tree(&mut self) {
+        self.tree = self
+            .selected
+            .as_ref()
+            .map(|pn| self.store.get_tree(pn, None).map_err(|e| e.to_string()));
+    }
 
+    fn tree_panel_ui(&self, ui: &mut egui::Ui) -> TreeResponse {
+        let Some(tree) = &self.tree else {
+            ui.label("Select an item to show its structure");
+            return TreeResponse::default();
+        };
+        let tree = match tree {
+            Ok(tree) => tree,
+            Err(e) => {
+                ui.colored_label(Color32::RED, e.as_str());
+                return TreeResponse::default();
+            }
+        };
+        ui.heading(tree.item.to_string());
+        let mut open = None;
+        ui.horizontal(|ui| {
+            if ui.button("Expand all").clicked() {
+                open = Some(true);
+            }
+            if ui.button("Collapse all").clicked() {
+                open = Some(false);
+            }
+        });
+        if let Some(e) = &self.tree_error {
+            ui.colored_label(Color32::RED, e.as_str());
+        }
+        ui.separator();
+        ScrollArea::both()
+            .show(ui, |ui| {
+                TreeView::new(tree)
+                    .open(open)
+                    .dragging(self.dragged.is_some(), self.drop_check.as_ref())
+                    .show(ui)
+            })
+            .inner
+    }
+
+    /// Check if the dragged item can be dropped on the hovered node, once
+    /// per node
+    fn check_drop(&mut self, hovered: Option<String>) {
+        let (Some(payload), Some(target)) = (&self.dragged, hovered) else {
+            self.drop_check = None;
+            return;
+        };
+        if let Some(check) = &self.drop_check {
+            if check.payload == *payload && check.target == target {
+                return;
+            }
+        }
+        let result = match &payload.parent {
+            Some(parent) if *parent == target => Err("already a child of this item".to_string()),
+            Some(parent) => self
+                .store
+                .check_move_child(parent, &target, &payload.pn)
+                .map_err(|e| e.to_string()),
+            None => self
+                .store
+                .check_add_child(&target, &payload.pn)
+                .map_err(|e| e.to_string()),
+        };
+        self.drop_check = Some(DropCheck {
+            payload: payload.clone(),
+            target,
+            result,
+        });
+    }
+
+    /// Drop the dragged item on the hovered node : an item of the search
+    /// list is added after asking its quantity, and a node of the tree is
+    /// moved
+    fn drop_dragged(&mut self) {
+        let (Some(payload), Some(check)) = (self.dragged.take(), self.drop_check.take()) else {
+            return;
+        };
+        if check.result.is_err() || check.payload != payload {
+            return;
+        }
+        match &payload.parent {
+            None => self.link_form = Some(LinkForm::new(&check.target, &payload.pn)),
+            Some(parent) => {
+                let result = self.store.move_child(parent, &check.target, &payload.pn);
+                self.tree_error = result.err().map(|e| e.to_string());
+                self.reload_tree();
+            }
+        }
+    }
+
+    fn ui(&mut self, ctx: &egui::Context) {
         // Side panel
         egui::SidePanel::left("SIDE PANEL").show(ctx, |ui| {
+            if ui.button("New item").clicked() {
+                self.new_item.open = true;
+            }
+            ui.separator();
             ui.heading("search item");
             ui.text_edit_singleline(&mut self.search_pattern);
-            ui.add(search_panel(&items));
+            // the list is filtered as the pattern is typed
+            let query = SearchQuery::new(&self.search_pattern);
+            match self.store.search(&query) {
+                Ok(items) => {
+                    let (clicked, dragged) = search_panel_ui(ui, &items, self.selected.as_deref());
+                    if let Some(pn) = clicked {
+                        self.select(&pn);
+                    }
+                    if dragged.is_some() {
+                        self.dragged = dragged;
+                    }
+                }
+                Err(e) => {
+                    ui.colored_label(Color32::RED, e.to_string());
+                }
+            }
         });
 
+        if let Some(item) = self.new_item.show(ctx, &mut self.store) {
+            self.select(item.pn());
+        }
+
+        // Item panel
+        if let Some(form) = &mut self.item_form {
+            let changed = egui::SidePanel::right("ITEM PANEL")
+                .show(ctx, |ui| form.ui(ui, &mut self.store))
+                .inner;
+            if changed {
+                self.reload_tree();
+            }
+        }
+
+        if let Some(form) = &mut self.link_form {
+            if let Some(added) = form.show(ctx, &mut self.store) {
+                self.link_form = None;
+                if added {
+                    self.reload_tree();
+                }
+            }
+        }
+
         // Central panel
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("My egui Application");
-            // ui.horizontal(|ui| {
-            //     let name_label = ui.label("Your name: ");
-            //     ui.text_edit_singleline(&mut self.name)
-            //         .labelled_by(name_label.id);
-            // });
-            // ui.add(egui::Slider::new(&mut self.age, 0..=120).text("age"));
-            // if ui.button("Click each year").clicked() {
-            //     self.age += 1;
-            // }
-            // ui.label(format!("Hello '{}', age {}", self.name, self.age));
-        });
+        let tree = egui::CentralPanel::default()
+            .show(ctx, |ui| self.tree_panel_ui(ui))
+            .inner;
+        if tree.drag_started.is_some() {
+            self.dragged = tree.drag_started;
+            self.tree_error = None;
+        }
+        self.check_drop(tree.hovered);
+        if ctx.input(|i| i.pointer.any_released()) {
+            self.drop_dragged();
+        }
     }
 }
 
-fn search_panel_ui(ui: &mut egui::Ui, items: &Vec<Item>) -> egui::Response {
-    let desired_size = ui.spacing().interact_size.y * egui::vec2(2.0, 1.0);
-    let (rect, mut response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
+/// Show the items found, and return the PN of the item clicked and the item
+/// which started to be dragged, if any
+fn search_panel_ui(
+    ui: &mut egui::Ui,
+    items: &[Item],
+    selected: Option<&str>,
+) -> (Option<String>, Option<DragPayload>) {
+    let mut clicked = None;
+    let mut dragged = None;
     ScrollArea::vertical().show(ui, |ui| {
         for item in items {
-            ui.label(item.to_string());
+            let is_selected = selected == Some(item.pn());
+            let label = item.to_string();
+            let response = ui.selectable_label(is_selected, &label);
+            if response.clicked() {
+                clicked = Some(item.pn().to_string());
+            }
+            let id = Id::new(("search item", item.pn()));
+            if drag_source(ui, id, response.rect, &label).drag_started() {
+                dragged = Some(DragPayload {
+                    pn: item.pn().to_string(),
+                    parent: None,
+                });
+            }
         }
     });
-    response
-}
-
-fn search_panel(items: &Vec<Item>) -> impl egui::Widget + '_ {
-    move |ui: &mut egui::Ui| search_panel_ui(ui, items)
+    (clicked, dragged)
 }
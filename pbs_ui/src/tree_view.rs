@@ -0,0 +1,186 @@
+//! Multi-level product tree of the selected item, in the central panel
+
+use std::collections::HashMap;
+
+use eframe::egui;
+use egui::{collapsing_header::CollapsingState, Color32, Id, RichText, Stroke};
+use pbs_core::{Item, ItemMaturity, Quantity, TreeNode};
+
+use crate::dnd::{drag_source, DragPayload, DropCheck};
+
+/// Background color of the badge of a maturity
+fn maturity_color(maturity: ItemMaturity) -> Color32 {
+    match maturity {
+        ItemMaturity::InProgress => Color32::from_rgb(0xff, 0xf2, 0xcc),
+        ItemMaturity::InReview => Color32::from_rgb(0xda, 0xe8, 0xfc),
+        ItemMaturity::Released => Color32::from_rgb(0xd5, 0xe8, 0xd4),
+        ItemMaturity::Obsolete => Color32::from_rgb(0xe0, 0xe0, 0xe0),
+    }
+}
+
+/// Show the maturity of an item as a colored badge
+pub fn maturity_badge(ui: &mut egui::Ui, maturity: ItemMaturity) -> egui::Response {
+    let text = RichText::new(format!(" {maturity} "))
+        .small()
+        .color(Color32::BLACK)
+        .background_color(maturity_color(maturity));
+    ui.label(text)
+}
+
+/// What happened in the tree during a frame
+#[derive(Default)]
+pub struct TreeResponse {
+    /// A node started to be dragged
+    pub drag_started: Option<DragPayload>,
+    /// PN of the node under the pointer while an item is dragged
+    pub hovered: Option<String>,
+}
+
+/// The tree of an item, where each sub-assembly can be collapsed
+///
+/// Sub-assemblies used several times in the tree are collapsed by default,
+/// so their structure is only expanded where it is looked at. The nodes can
+/// be dragged to another parent, and items can be dropped on the nodes.
+pub struct TreeView<'a> {
+    tree: &'a TreeNode,
+    /// Number of nodes of each item in the tree
+    usages: HashMap<&'a Item, usize>,
+    /// Expand or collapse all the sub-assemblies for this frame
+    open: Option<bool>,
+    /// An item is being dragged
+    dragging: bool,
+    /// Whether the dragged item can be dropped on the hovered node
+    check: Option<&'a DropCheck>,
+}
+
+impl<'a> TreeView<'a> {
+    pub fn new(tree: &'a TreeNode) -> Self {
+        let mut usages = HashMap::new();
+        for node in tree.iter() {
+            *usages.entry(&node.item).or_insert(0) += 1;
+        }
+        TreeView {
+            tree,
+            usages,
+            open: None,
+            dragging: false,
+            check: None,
+        }
+    }
+
+    /// Expand (`Some(true)`) or collapse (`Some(false)`) all the
+    /// sub-assemblies
+    pub fn open(mut self, open: Option<bool>) -> Self {
+        self.open = open;
+        self
+    }
+
+    /// Highlight the node under the pointer as a drop target, with the
+    /// result of the check of the drop on this node, if known
+    pub fn dragging(mut self, dragging: bool, check: Option<&'a DropCheck>) -> Self {
+        self.dragging = dragging;
+        self.check = check;
+        self
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) -> TreeResponse {
+        let mut response = TreeResponse::default();
+        self.node_ui(ui, self.tree, None, "tree", &mut response);
+        response
+    }
+
+    fn is_shared(&self, item: &Item) -> bool {
+        self.usages.get(item).copied().unwrap_or(0) > 1
+    }
+
+    /// Show a node and its children, `path` identifying the branch of the
+    /// node so the state of each usage of a shared item is kept apart
+    fn node_ui(
+        &self,
+        ui: &mut egui::Ui,
+        node: &TreeNode,
+        parent: Option<&Item>,
+        path: &str,
+        response: &mut TreeResponse,
+    ) {
+        let path = format!("{path}/{}", node.item.pn());
+        if node.children.is_empty() {
+            self.row_ui(ui, node, parent, &path, response);
+            return;
+        }
+        let id = ui.make_persistent_id(&path);
+        let default_open = node.level == 0 || !self.is_shared(&node.item);
+        let mut state = CollapsingState::load_with_default_open(ui.ctx(), id, default_open);
+        if let Some(open) = self.open {
+            state.set_open(open);
+        }
+        state
+            .show_header(ui, |ui| self.row_ui(ui, node, parent, &path, response))
+            .body(|ui| {
+                for child in &node.children {
+                    self.node_ui(ui, child, Some(&node.item), &path, response);
+                }
+            });
+    }
+
+    /// Show a row of the tree, which can be dragged unless it is the root,
+    /// and can receive the dragged item
+    fn row_ui(
+        &self,
+        ui: &mut egui::Ui,
+        node: &TreeNode,
+        parent: Option<&Item>,
+        path: &str,
+        response: &mut TreeResponse,
+    ) {
+        let id = Id::new(("tree row", path));
+        let rect = ui.horizontal(|ui| self.content_ui(ui, node)).response.rect;
+        if let Some(parent) = parent {
+            let label = node.item.to_string();
+            if drag_source(ui, id, rect, &label).drag_started() {
+                response.drag_started = Some(DragPayload {
+                    pn: node.item.pn().to_string(),
+                    parent: Some(parent.pn().to_string()),
+                });
+            }
+        }
+        if !self.dragging || !ui.rect_contains_pointer(rect) {
+            return;
+        }
+        response.hovered = Some(node.item.pn().to_string());
+        let check = self
+            .check
+            .filter(|check| check.target == node.item.pn())
+            .map(|check| &check.result);
+        let color = match check {
+            Some(Ok(())) => Color32::GREEN,
+            Some(Err(_)) => Color32::RED,
+            None => Color32::GRAY,
+        };
+        ui.painter()
+            .rect_stroke(rect.expand(2.0), 3.0, Stroke::new(2.0, color));
+        if let Some(Err(e)) = check {
+            egui::show_tooltip_text(ui.ctx(), id.with("drop"), e.as_str());
+        }
+    }
+
+    /// Show the quantities, the item and its maturity
+    fn content_ui(&self, ui: &mut egui::Ui, node: &TreeNode) {
+        if node.level > 0 {
+            ui.label(format!("{} ×", node.quantity));
+        }
+        ui.label(RichText::new(node.item.pn()).strong());
+        ui.label(format!("v{}", node.item.version()));
+        ui.label(node.item.name());
+        maturity_badge(ui, node.item.maturity());
+        let total = Quantity::new(node.total_quantity, node.item.unit());
+        if total != node.quantity {
+            ui.label(RichText::new(format!("(total {total})")).weak())
+                .on_hover_text("quantity in the root item");
+        }
+        if node.level > 0 && self.is_shared(&node.item) {
+            ui.label(RichText::new("shared").italics().weak())
+                .on_hover_text("this item is used several times in the tree");
+        }
+    }
+}